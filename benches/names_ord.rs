@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+
+use benchmark_from_crates::names::{new_bucket, new_wide, FeatureNamespace, Names};
+use cargo::util::interning::InternedString;
+use criterion::{criterion_group, criterion_main, Criterion};
+use semver_pubgrub::SemverCompatibility;
+
+/// Roughly the shape of what gets explored for a large root: many packages,
+/// each with several compatibility ranges, a few features and some wide requirements.
+fn large_root(reqs: &[semver::VersionReq]) -> Vec<Names<'_>> {
+    let mut names = Vec::new();
+    for c in 0..500 {
+        let crate_ = InternedString::new(&format!("crate{}", c % 100));
+        let parent = InternedString::new(&format!("parent{c}"));
+        for (major, req) in reqs.iter().enumerate() {
+            let compat = SemverCompatibility::from(&semver::Version::new(major as u64, 1, 0));
+            let bucket = new_bucket(crate_, compat, false);
+            names.push(bucket.with_default_features());
+            for feat in ["std", "derive", "dep:alloc"] {
+                names.push(bucket.with_features(FeatureNamespace::new(feat)));
+            }
+            names.push(bucket);
+            let wide = new_wide(crate_, req, parent, compat);
+            names.push(wide.with_features(FeatureNamespace::new("std")));
+            names.push(wide);
+        }
+    }
+    names
+}
+
+fn names_ord(c: &mut Criterion) {
+    let reqs: Vec<semver::VersionReq> = ["^0.1", "^1", "^2", "^3"]
+        .iter()
+        .map(|r| r.parse().unwrap())
+        .collect();
+    let names = large_root(&reqs);
+
+    c.bench_function("sort names", |b| {
+        b.iter(|| {
+            let mut names = names.clone();
+            names.sort();
+            names
+        })
+    });
+    // The order `Names` used to implement: crate names, then the `Display` strings.
+    c.bench_function("sort names by baseline order", |b| {
+        b.iter(|| {
+            let mut names = names.clone();
+            names.sort_by(|a, b| {
+                a.crate_()
+                    .cmp(&b.crate_())
+                    .then_with(|| a.to_string().cmp(&b.to_string()))
+            });
+            names
+        })
+    });
+    c.bench_function("btreemap of names", |b| {
+        b.iter(|| {
+            names
+                .iter()
+                .map(|n| (n.clone(), ()))
+                .collect::<BTreeMap<_, _>>()
+        })
+    });
+}
+
+criterion_group!(benches, names_ord);
+criterion_main!(benches);
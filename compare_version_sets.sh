@@ -0,0 +1,13 @@
+# Runs the benchmark with `Rc` and with `Arc` version sets and compares the two.
+# Any extra arguments are passed to both runs, for example `--filter serde -t 10`.
+set -e
+
+cargo b -r
+./target/release/benchmark_from_crates "$@"
+rc_out=$(ls -t out*.csv | head -n 1)
+
+cargo b -r --features arc_version_set
+./target/release/benchmark_from_crates "$@"
+arc_out=$(ls -t out*_arc_version_set*.csv | head -n 1)
+
+./target/release/benchmark_from_crates compare "$rc_out" "$arc_out"
@@ -1,8 +1,10 @@
+use std::collections::BTreeSet;
 use std::sync::OnceLock;
 use std::task::Poll;
 
 use anyhow::bail;
 use cargo::core::dependency::DepKind;
+use cargo::core::resolver::features::{CliFeatures, RequestedFeatures};
 use cargo::core::resolver::{self, ResolveOpts, VersionPreferences};
 use cargo::core::Resolve;
 use cargo::core::ResolveVersion;
@@ -74,6 +76,33 @@ pub fn resolve<'c>(
     name: InternedString,
     ver: &semver::Version,
     dp: &mut crate::Index<'c>,
+) -> CargoResult<Resolve> {
+    let resolve_opts = ResolveOpts::new(
+        dp.dev_deps,
+        RequestedFeatures::CliFeatures(CliFeatures::new_all(true)),
+    );
+    resolve_with_opts(name, ver, dp, resolve_opts)
+}
+
+/// Resolves `name@ver` the way it is resolved as a dependency, with its default features and
+/// without its dev dependencies.
+pub fn resolve_as_dependency<'c>(
+    name: InternedString,
+    ver: &semver::Version,
+    dp: &mut crate::Index<'c>,
+) -> CargoResult<Resolve> {
+    let resolve_opts = ResolveOpts::new(
+        false,
+        RequestedFeatures::CliFeatures(CliFeatures::new_all(false)),
+    );
+    resolve_with_opts(name, ver, dp, resolve_opts)
+}
+
+fn resolve_with_opts<'c>(
+    name: InternedString,
+    ver: &semver::Version,
+    dp: &mut crate::Index<'c>,
+    resolve_opts: ResolveOpts,
 ) -> CargoResult<Resolve> {
     let Some(pack) = dp.crates.get(&name) else {
         bail!("No package found named '{name}'");
@@ -83,16 +112,37 @@ pub fn resolve<'c>(
     };
     let new_id = summary.package_id().with_source_id(other_registry_loc());
     let summary = summary.override_id(new_id);
+    let mut version_prefs = VersionPreferences::default();
+    for (name, vers) in &dp.preferred {
+        for ver in vers {
+            version_prefs.prefer_package_id(PackageId::new(*name, ver.clone(), registry_loc()));
+        }
+    }
     resolver::resolve(
-        &[(summary, ResolveOpts::everything())],
+        &[(summary, resolve_opts)],
         &[],
         dp,
-        &VersionPreferences::default(),
+        &version_prefs,
         ResolveVersion::with_rust_version(None),
         None,
     )
 }
 
+/// Whether `id` came from a registry, rather than being the root.
+/// The root is in its own source in `resolve`, and is a path package in a workspace.
+pub fn is_registry_package(id: PackageId) -> bool {
+    id.source_id().is_registry() && id.source_id() != other_registry_loc()
+}
+
+/// The packages in `resolve` that came from a registry, leaving out the root.
+pub fn registry_packages(resolve: &Resolve) -> BTreeSet<(InternedString, semver::Version)> {
+    resolve
+        .iter()
+        .filter(|id| is_registry_package(*id))
+        .map(|id| (id.name(), id.version().clone()))
+        .collect()
+}
+
 impl From<&crate::index_data::Dependency> for Dependency {
     fn from(value: &crate::index_data::Dependency) -> Self {
         let mut out = Dependency::parse(value.package_name, None, registry_loc()).unwrap();
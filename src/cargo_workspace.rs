@@ -0,0 +1,179 @@
+//! Resolves a root the way `cargo generate-lockfile` does, for a check on the shortcut taken by
+//! `cargo_resolver::resolve`.
+//!
+//! The root is written out as the only package of a workspace, using the same stub manifest as
+//! `registry_server`, and crates.io is replaced by a `registry_server` for the index. Cargo then
+//! goes through its whole path, with the workspace loading, resolving, checking the result and
+//! writing and reading back the `Cargo.lock`.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use cargo::{
+    core::{
+        compiler::{CompileKind, RustcTargetData},
+        resolver::features::{CliFeatures, FeaturesFor, ForceAllTargets, HasDevUnits},
+        Resolve, Shell, Workspace,
+    },
+    ops,
+    util::interning::InternedString,
+    GlobalContext,
+};
+
+use crate::{
+    cargo_resolver, feature_resolver::SplitFeatureSets, index_data, registry_server, IndexMapLookup,
+};
+
+/// What `resolve_features` keeps between roots. Making the target data runs `rustc`, so it is
+/// made once for each thread and workspace. The context is leaked so the target data can
+/// borrow it for the rest of the run.
+struct FeatureContext {
+    gctx: &'static GlobalContext,
+    target_data: Option<RustcTargetData<'static>>,
+}
+
+thread_local! {
+    static FEATURE_CONTEXTS: RefCell<HashMap<PathBuf, FeatureContext>> = RefCell::new(HashMap::new());
+}
+
+#[derive(Clone, Debug)]
+pub struct CargoWorkspace {
+    /// Where the root package is written.
+    root: PathBuf,
+    /// The `CARGO_HOME`, with a config replacing crates.io.
+    home: PathBuf,
+}
+
+impl CargoWorkspace {
+    /// Sets up a workspace in `dir` that resolves against the sparse index at `index_url`.
+    /// Each workspace takes cargo's package cache lock while resolving, so every thread
+    /// should have its own `dir`.
+    pub fn new(dir: &Path, index_url: &str) -> anyhow::Result<Self> {
+        let dir = std::path::absolute(dir)?;
+        let root = dir.join("root");
+        let home = dir.join("home");
+        std::fs::create_dir_all(&root)?;
+        std::fs::create_dir_all(&home)?;
+        let config = format!(
+            "[source.crates-io]\n\
+             replace-with = \"benchmark\"\n\
+             \n\
+             [source.benchmark]\n\
+             registry = \"sparse+{index_url}\"\n"
+        );
+        std::fs::write(home.join("config.toml"), config)?;
+        Ok(Self { root, home })
+    }
+
+    /// Resolves `name@ver` with all of its features, and returns what was written to the
+    /// `Cargo.lock`. The root is written out by `root_files`.
+    pub fn generate_lockfile(
+        &self,
+        crates: &IndexMapLookup,
+        name: InternedString,
+        ver: &semver::Version,
+        dev_deps: bool,
+    ) -> anyhow::Result<Resolve> {
+        let (version, _) = crates
+            .get(&name)
+            .and_then(|vers| vers.get(ver))
+            .with_context(|| format!("No version found for package '{name}@{ver}'"))?;
+
+        let _ = std::fs::remove_dir_all(&self.root);
+        std::fs::create_dir_all(self.root.join("src"))?;
+        for (path, contents) in root_files(version, dev_deps) {
+            std::fs::write(self.root.join(path), contents)?;
+        }
+
+        let shell = Shell::from_write(Box::new(std::io::sink()));
+        let gctx = GlobalContext::new(shell, self.root.clone(), self.home.clone());
+        let ws = Workspace::new(&self.root.join("Cargo.toml"), &gctx)?;
+        ops::generate_lockfile(&ws)?;
+        ops::load_pkg_lockfile(&ws)?.context("cargo did not write a Cargo.lock")
+    }
+
+    /// Runs cargo's feature resolver on the root written by the last `generate_lockfile`,
+    /// as for a build of all targets of the root on the host, with all of its features.
+    /// This downloads the stub crates, unlike `generate_lockfile`.
+    pub fn resolve_features(&self, dev_deps: bool) -> anyhow::Result<SplitFeatureSets> {
+        FEATURE_CONTEXTS.with_borrow_mut(|contexts| {
+            let context = contexts.entry(self.root.clone()).or_insert_with(|| {
+                let shell = Shell::from_write(Box::new(std::io::sink()));
+                let gctx = GlobalContext::new(shell, self.root.clone(), self.home.clone());
+                FeatureContext {
+                    gctx: Box::leak(Box::new(gctx)),
+                    target_data: None,
+                }
+            });
+            self.resolve_features_in(context, dev_deps)
+        })
+    }
+
+    fn resolve_features_in(
+        &self,
+        context: &mut FeatureContext,
+        dev_deps: bool,
+    ) -> anyhow::Result<SplitFeatureSets> {
+        let ws = Workspace::new(&self.root.join("Cargo.toml"), context.gctx)?;
+        let requested_kinds = [CompileKind::Host];
+        if context.target_data.is_none() {
+            context.target_data = Some(RustcTargetData::new(&ws, &requested_kinds)?);
+        }
+        let target_data = context.target_data.as_mut().unwrap();
+        let specs: Vec<_> = ws.members().map(|p| p.package_id().to_spec()).collect();
+        let has_dev_units = if dev_deps {
+            HasDevUnits::Yes
+        } else {
+            HasDevUnits::No
+        };
+        let resolve = ops::resolve_ws_with_opts(
+            &ws,
+            target_data,
+            &requested_kinds,
+            &CliFeatures::new_all(true),
+            &specs,
+            has_dev_units,
+            ForceAllTargets::Yes,
+            false,
+        )?;
+
+        let mut sets = SplitFeatureSets::new();
+        let registry_packages = resolve
+            .targeted_resolve
+            .iter()
+            .filter(|id| cargo_resolver::is_registry_package(*id));
+        for id in registry_packages {
+            for for_host in [false, true] {
+                let features = resolve
+                    .resolved_features
+                    .activated_features_unverified(id, FeaturesFor::from_for_host(for_host));
+                if let Some(features) = features {
+                    let key = ((id.name(), id.version().clone()), for_host);
+                    sets.insert(key, features.into_iter().collect());
+                }
+            }
+        }
+        Ok(sets)
+    }
+}
+
+/// The files `generate_lockfile` writes for the root: the stub's manifest as the only member of
+/// a workspace, and its sources. Cargo always resolves dev dependencies for a lock file, so
+/// without `dev_deps` they are left out of the manifest.
+pub fn root_files(version: &index_data::Version, dev_deps: bool) -> Vec<(&'static str, String)> {
+    let version = if dev_deps {
+        version.clone()
+    } else {
+        version.clone().without_dev_deps()
+    };
+    // `cargo generate-lockfile` resolves every feature of the workspace members.
+    // The resolver setting only matters to `resolve_features`.
+    let manifest = registry_server::manifest(&version) + "\n[workspace]\nresolver = \"2\"\n";
+    let mut files = vec![("Cargo.toml", manifest)];
+    files.extend(registry_server::stub_sources(&version));
+    files
+}
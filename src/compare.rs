@@ -0,0 +1,195 @@
+use std::{collections::HashMap, io::Read, path::Path};
+
+use itertools::Itertools as _;
+
+/// The columns of an `OutputSummary` CSV that are needed to compare two runs.
+/// Any other columns in the file are ignored.
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct ComparedRow {
+    pub name: String,
+    pub ver: semver::Version,
+    pub time: f32,
+    pub succeeded: bool,
+    pub deps: usize,
+}
+
+pub fn read_summary<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<ComparedRow>> {
+    read_summary_from(std::fs::File::open(path)?)
+}
+
+pub fn read_summary_from<R: Read>(data: R) -> anyhow::Result<Vec<ComparedRow>> {
+    let mut reader = csv::Reader::from_reader(data);
+    let mut rows = Vec::new();
+    for row in reader.deserialize() {
+        rows.push(row?);
+    }
+    Ok(rows)
+}
+
+pub struct RowDelta {
+    pub before: ComparedRow,
+    pub after: ComparedRow,
+}
+
+impl RowDelta {
+    pub fn time_delta(&self) -> f32 {
+        self.after.time - self.before.time
+    }
+
+    pub fn outcome_changed(&self) -> bool {
+        self.before.succeeded != self.after.succeeded || self.before.deps != self.after.deps
+    }
+}
+
+/// Two runs joined on `(name, ver)`.
+pub struct Comparison {
+    pub matched: Vec<RowDelta>,
+    pub only_before: Vec<ComparedRow>,
+    pub only_after: Vec<ComparedRow>,
+}
+
+impl Comparison {
+    pub fn new(before: Vec<ComparedRow>, after: Vec<ComparedRow>) -> Self {
+        let mut after: HashMap<(String, semver::Version), ComparedRow> = after
+            .into_iter()
+            .map(|r| ((r.name.clone(), r.ver.clone()), r))
+            .collect();
+        let mut matched = Vec::new();
+        let mut only_before = Vec::new();
+        for before in before {
+            match after.remove(&(before.name.clone(), before.ver.clone())) {
+                Some(after) => matched.push(RowDelta { before, after }),
+                None => only_before.push(before),
+            }
+        }
+        let only_after = after
+            .into_values()
+            .sorted_by(|a, b| (&a.name, &a.ver).cmp(&(&b.name, &b.ver)))
+            .collect();
+        Self {
+            matched,
+            only_before,
+            only_after,
+        }
+    }
+
+    pub fn total_before(&self) -> f32 {
+        self.matched.iter().map(|r| r.before.time).sum()
+    }
+
+    pub fn total_after(&self) -> f32 {
+        self.matched.iter().map(|r| r.after.time).sum()
+    }
+
+    /// How much slower the second run was over the rows both runs processed, in percent.
+    pub fn regression_percent(&self) -> f32 {
+        let before = self.total_before();
+        if before <= 0.0 {
+            return 0.0;
+        }
+        (self.total_after() - before) / before * 100.0
+    }
+
+    /// The exit code of `compare`, non-zero if the regression is over `threshold` percent.
+    pub fn exit_code(&self, threshold: f32) -> i32 {
+        if self.regression_percent() > threshold {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// The `pct` percentile of the times of the matched rows, before and after.
+    pub fn percentile(&self, pct: f32) -> (f32, f32) {
+        let sorted = |time: fn(&RowDelta) -> f32| {
+            self.matched
+                .iter()
+                .map(time)
+                .sorted_by(f32::total_cmp)
+                .collect_vec()
+        };
+        (
+            percentile(&sorted(|r| r.before.time), pct),
+            percentile(&sorted(|r| r.after.time), pct),
+        )
+    }
+
+    pub fn print_report(&self, top: usize) {
+        println!("!!!!!!!!!! Comparison !!!!!!!!!!");
+        println!("    matched rows: {}", self.matched.len());
+        println!("  only in before: {}", self.only_before.len());
+        println!("   only in after: {}", self.only_after.len());
+
+        let p = |n: &str, before: f32, after: f32| {
+            println!(
+                "{n:>20} time: {:>8.2}s -> {:>8.2}s ({:>+8.2}s)",
+                before,
+                after,
+                after - before
+            )
+        };
+        p("Total", self.total_before(), self.total_after());
+        for pct in [50.0, 90.0, 99.0, 100.0] {
+            let (before, after) = self.percentile(pct);
+            p(&format!("p{pct}"), before, after);
+        }
+        println!("    total change: {:>+6.2}%", self.regression_percent());
+
+        let by_delta = self
+            .matched
+            .iter()
+            .sorted_by(|a, b| b.time_delta().total_cmp(&a.time_delta()))
+            .collect_vec();
+        println!("!!!!!!!!!! Biggest regressions !!!!!!!!!!");
+        for r in by_delta.iter().take(top).filter(|r| r.time_delta() > 0.0) {
+            print_row(r);
+        }
+        println!("!!!!!!!!!! Biggest improvements !!!!!!!!!!");
+        for r in by_delta
+            .iter()
+            .rev()
+            .take(top)
+            .filter(|r| r.time_delta() < 0.0)
+        {
+            print_row(r);
+        }
+
+        let changed = self
+            .matched
+            .iter()
+            .filter(|r| r.outcome_changed())
+            .collect_vec();
+        println!("!!!!!!!!!! Changed outcome: {} !!!!!!!!!!", changed.len());
+        for r in changed {
+            println!(
+                "{}@{}: succeeded {} -> {}, deps {} -> {}",
+                r.before.name,
+                r.before.ver,
+                r.before.succeeded,
+                r.after.succeeded,
+                r.before.deps,
+                r.after.deps
+            );
+        }
+    }
+}
+
+fn print_row(r: &RowDelta) {
+    println!(
+        "{}@{}: {:.3}s -> {:.3}s ({:+.3}s)",
+        r.before.name,
+        r.before.ver,
+        r.before.time,
+        r.after.time,
+        r.time_delta()
+    );
+}
+
+/// Nearest-rank percentile of an already sorted slice.
+fn percentile(sorted: &[f32], pct: f32) -> f32 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f32).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
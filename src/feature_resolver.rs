@@ -0,0 +1,143 @@
+//! An emulation of cargo's feature resolver version 2 on top of a pubgrub solution.
+//!
+//! The `Names` encoding unifies the features of a package across all the ways it is used,
+//! like feature resolver 1 does. Resolver 2 (and 3, which only changes version selection)
+//! keeps the features of packages built for the host, through build dependencies, apart from
+//! those of packages built for the target. This reruns the feature activation over the versions
+//! pubgrub picked, keeping the two apart.
+//!
+//! Proc-macros are also built for the host, but the index does not say which crates are
+//! proc-macros, so they are treated like any other crate. The stub crates used by
+//! `cargo_workspace` are not proc-macros either, so the comparison with cargo is like for like.
+//! Platform specific dependencies are all included, as with `--all-targets` in cargo.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cargo::util::interning::InternedString;
+use crates_index::DependencyKind;
+
+use crate::{graph::PackageKey, index_data, names::Names, Index, Solution};
+
+/// The features turned on for each package, by whether it is built for the host.
+pub type SplitFeatureSets = BTreeMap<(PackageKey, bool), BTreeSet<InternedString>>;
+
+/// What a set of features of one version turns on.
+#[derive(Default)]
+struct Activation {
+    /// The features, including the ones turned on by other features.
+    features: BTreeSet<InternedString>,
+    /// The optional dependencies that are turned on.
+    deps: BTreeSet<InternedString>,
+    /// The features asked of each dependency, including weak requests (`dep?/feat`),
+    /// which only count if the dependency is turned on some other way.
+    dep_features: BTreeMap<InternedString, BTreeSet<InternedString>>,
+}
+
+fn activate(index_ver: &index_data::Version, features: &BTreeSet<InternedString>) -> Activation {
+    let mut out = Activation::default();
+    let mut stack: Vec<InternedString> = features.iter().copied().collect();
+    while let Some(feat) = stack.pop() {
+        if !out.features.insert(feat) {
+            continue;
+        }
+        for val in index_ver
+            .features
+            .get(&feat)
+            .into_iter()
+            .flat_map(|v| v.iter())
+        {
+            if let Some((dep, dep_feat)) = val.split_once('/') {
+                let (dep, weak) = match dep.strip_suffix('?') {
+                    Some(dep) => (dep, true),
+                    None => (dep, false),
+                };
+                if !weak {
+                    out.deps.insert(dep.into());
+                    if index_ver.features.contains_key(dep) {
+                        stack.push(dep.into());
+                    }
+                }
+                out.dep_features
+                    .entry(dep.into())
+                    .or_default()
+                    .insert(dep_feat.into());
+            } else if let Some(dep) = val.strip_prefix("dep:") {
+                out.deps.insert(dep.into());
+            } else {
+                stack.push(*val);
+            }
+        }
+    }
+    out
+}
+
+/// Runs feature resolution over the packages in `map`, with build dependencies decoupled.
+/// The root is built for the target with all of its features, and is left out of the result.
+pub(crate) fn emulate_v2<'c>(
+    dp: &Index<'c>,
+    root: &Names<'c>,
+    map: &Solution<'c>,
+) -> SplitFeatureSets {
+    let root_key: (PackageKey, bool) = ((root.crate_(), map[root].clone()), false);
+    let root_ver = dp.get_version(root.crate_().as_str(), &map[root]).unwrap();
+
+    let mut sets = SplitFeatureSets::new();
+    sets.insert(
+        root_key.clone(),
+        root_ver.features.keys().copied().collect(),
+    );
+    let mut stack = vec![root_key.clone()];
+    while let Some(key) = stack.pop() {
+        let ((name, ver), for_host) = &key;
+        let index_ver = dp.get_version(name.as_str(), ver).unwrap();
+        let activation = activate(index_ver, &sets[&key]);
+        for dep in index_ver.deps.iter() {
+            if dep.kind == DependencyKind::Dev && !(key == root_key && dp.dev_deps) {
+                continue;
+            }
+            if dep.optional && !activation.deps.contains(&dep.name) {
+                continue;
+            }
+            let dep_for_host = *for_host || dep.kind == DependencyKind::Build;
+            let mut features: BTreeSet<InternedString> = dep
+                .features
+                .iter()
+                .copied()
+                .filter(|f| !f.is_empty())
+                .collect();
+            features.extend(activation.dep_features.get(&dep.name).into_iter().flatten());
+
+            let Some(target) = dp.dep_target(dep, *name, ver, map) else {
+                continue;
+            };
+            let target_ver = dp.get_version(dep.package_name.as_str(), &target).unwrap();
+            if dep.default_features && target_ver.features.contains_key("default") {
+                features.insert("default".into());
+            }
+            let target_key = ((dep.package_name, target), dep_for_host);
+            let is_new = !sets.contains_key(&target_key);
+            let set = sets.entry(target_key.clone()).or_default();
+            let len = set.len();
+            set.extend(features);
+            if is_new || set.len() != len {
+                stack.push(target_key);
+            }
+        }
+    }
+
+    sets.remove(&root_key);
+    sets.into_iter()
+        .map(|(key, features)| {
+            let index_ver = dp.get_version(key.0 .0.as_str(), &key.0 .1).unwrap();
+            (key, activate(index_ver, &features).features)
+        })
+        .collect()
+}
+
+/// How many `(package, for_host)` pairs have different features, or are only in one of them.
+pub fn count_differences(a: &SplitFeatureSets, b: &SplitFeatureSets) -> usize {
+    let keys: BTreeSet<_> = a.keys().chain(b.keys()).collect();
+    keys.into_iter()
+        .filter(|key| a.get(*key) != b.get(*key))
+        .count()
+}
@@ -0,0 +1,88 @@
+//! The features each resolver turned on, and where they differ.
+//!
+//! The root is left out of both sides. It is resolved with all of its features, which pubgrub
+//! does not record as `BucketFeatures` packages.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cargo::{core::Resolve, util::interning::InternedString};
+
+use crate::{
+    cargo_resolver,
+    names::{FeatureNamespace, Names},
+    Solution,
+};
+
+/// The features turned on for each selected package, by name and version.
+pub type FeatureSets = BTreeMap<(InternedString, semver::Version), BTreeSet<InternedString>>;
+
+/// Reads the features out of the `BucketFeatures` packages in a pubgrub solution.
+pub(crate) fn pub_feature_sets(map: &Solution<'_>) -> FeatureSets {
+    let mut sets = FeatureSets::new();
+    for (name, ver) in map {
+        match name {
+            Names::Bucket(name, _, false) => {
+                sets.entry((*name, ver.clone())).or_default();
+            }
+            Names::BucketFeatures(name, _, FeatureNamespace::Feat(feat)) => {
+                sets.entry((*name, ver.clone()))
+                    .or_default()
+                    .insert((*feat).into());
+            }
+            _ => {}
+        }
+    }
+    sets
+}
+
+/// Reads the features cargo turned on for each package in `resolve` that came from the index.
+/// Like the `BucketFeatures` packages, these include `default` and the implicit features of
+/// optional dependencies, so the two sides compare like for like.
+pub fn cargo_feature_sets(resolve: &Resolve) -> FeatureSets {
+    resolve
+        .iter()
+        .filter(|id| cargo_resolver::is_registry_package(*id))
+        .map(|id| {
+            let features = resolve.features(id).iter().copied().collect();
+            ((id.name(), id.version().clone()), features)
+        })
+        .collect()
+}
+
+/// A package selected by both resolvers, for which they picked different features.
+#[derive(serde::Serialize, Debug)]
+pub struct FeatureDifference {
+    pub name: InternedString,
+    pub version: semver::Version,
+    pub pubgrub: BTreeSet<InternedString>,
+    pub cargo: BTreeSet<InternedString>,
+}
+
+/// The packages selected by both resolvers that have different features turned on.
+/// Packages only one of them selected are left out, those are a difference in versions.
+pub fn differences(pubgrub: &FeatureSets, cargo: &FeatureSets) -> Vec<FeatureDifference> {
+    pubgrub
+        .iter()
+        .filter_map(|(key, pub_features)| {
+            let cargo_features = cargo.get(key)?;
+            (pub_features != cargo_features).then(|| FeatureDifference {
+                name: key.0,
+                version: key.1.clone(),
+                pubgrub: pub_features.clone(),
+                cargo: cargo_features.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Writes the differences for the root `name@ver` to `out/features`.
+pub fn write_differences_file(
+    name: InternedString,
+    ver: &semver::Version,
+    differences: &[FeatureDifference],
+) {
+    std::fs::create_dir_all("out/features").unwrap();
+    let file_name = format!("out/features/{name}@{ver}.ron");
+    let data = ron::ser::to_string_pretty(differences, ron::ser::PrettyConfig::new()).unwrap();
+    std::fs::write(file_name, data).unwrap();
+}
@@ -0,0 +1,312 @@
+//! The dependency graph of a resolution, with the kind of each edge.
+//!
+//! Cargo's `Resolve` records its edges. A pubgrub solution is just the selected packages, so
+//! its edges are worked out again from the index: a dependency of a selected package points
+//! at the version of the package pubgrub used for that dependency.
+//!
+//! Graphs can be written as Graphviz DOT, and as JSON with a list of nodes and a list of edges.
+//! The JSON nodes also say how each package is reached from the root.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use cargo::core::{dependency::DepKind, Resolve};
+use cargo::util::interning::InternedString;
+use crates_index::DependencyKind;
+
+use crate::{
+    cargo_resolver,
+    names::{FeatureNamespace, Names},
+    Index, Solution,
+};
+
+pub type PackageKey = (InternedString, semver::Version);
+
+pub struct DependencyGraph {
+    pub root: PackageKey,
+    /// Every selected package, with the packages it depends on.
+    pub edges: BTreeMap<PackageKey, BTreeMap<PackageKey, Edge>>,
+}
+
+/// All the dependencies from one package on another, merged together.
+#[derive(Clone, Debug, Default)]
+pub struct Edge {
+    pub kinds: BTreeSet<DepKind>,
+    /// The features asked for by the dependencies.
+    pub features: BTreeSet<InternedString>,
+    pub default_features: bool,
+    /// Whether any of the dependencies is optional.
+    pub optional: bool,
+}
+
+impl Edge {
+    fn add(
+        &mut self,
+        kind: DepKind,
+        features: impl IntoIterator<Item = InternedString>,
+        default_features: bool,
+        optional: bool,
+    ) {
+        self.kinds.insert(kind);
+        self.features
+            .extend(features.into_iter().filter(|f| !f.is_empty()));
+        self.default_features |= default_features;
+        self.optional |= optional;
+    }
+}
+
+/// How a package can be reached from the root.
+#[derive(serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reach {
+    /// Only through normal dependencies, so it is built into the root.
+    pub normal: bool,
+    /// Through at least one build dependency, so it is built for the host.
+    pub build: bool,
+    /// Through one of the root's dev dependencies, so it is only needed for tests.
+    pub dev: bool,
+}
+
+/// How many packages of a resolution are needed for what.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Footprint {
+    /// Packages needed to build the root, through normal or build dependencies.
+    pub production: usize,
+    /// Packages needed to build the root that are built for the host.
+    pub build: usize,
+    /// Packages that are only needed for the root's tests.
+    pub dev_only: usize,
+}
+
+fn kind_from_index(kind: DependencyKind) -> DepKind {
+    match kind {
+        DependencyKind::Normal => DepKind::Normal,
+        DependencyKind::Dev => DepKind::Development,
+        DependencyKind::Build => DepKind::Build,
+    }
+}
+
+impl DependencyGraph {
+    pub fn from_cargo(resolve: &Resolve) -> Self {
+        let key = |id: cargo::core::PackageId| (id.name(), id.version().clone());
+        let root = resolve
+            .iter()
+            .find(|id| !cargo_resolver::is_registry_package(*id))
+            .expect("the root is in the resolve");
+        let edges = resolve
+            .iter()
+            .map(|id| {
+                let deps = resolve
+                    .deps(id)
+                    .map(|(dep_id, deps)| {
+                        let mut edge = Edge::default();
+                        for d in deps {
+                            edge.add(
+                                d.kind(),
+                                d.features().iter().copied(),
+                                d.uses_default_features(),
+                                d.is_optional(),
+                            );
+                        }
+                        (key(dep_id), edge)
+                    })
+                    .collect();
+                (key(id), deps)
+            })
+            .collect();
+        Self {
+            root: key(root),
+            edges,
+        }
+    }
+
+    pub(crate) fn from_pubgrub<'c>(dp: &Index<'c>, root: &Names<'c>, map: &Solution<'c>) -> Self {
+        let mut selected: BTreeMap<InternedString, BTreeSet<semver::Version>> = BTreeMap::new();
+        let mut active_deps: BTreeSet<(InternedString, semver::Version, &str)> = BTreeSet::new();
+        for (name, ver) in map {
+            match name {
+                Names::Bucket(name, _, _) => {
+                    selected.entry(*name).or_default().insert(ver.clone());
+                }
+                Names::BucketFeatures(name, _, FeatureNamespace::Dep(dep)) => {
+                    active_deps.insert((*name, ver.clone(), *dep));
+                }
+                _ => {}
+            }
+        }
+        let root_key = (root.crate_(), map[root].clone());
+
+        let mut edges = BTreeMap::new();
+        for (name, vers) in &selected {
+            for ver in vers {
+                let key = (*name, ver.clone());
+                let is_root = key == root_key;
+                let index_ver = dp.get_version(name.as_str(), ver).unwrap();
+                let mut deps: BTreeMap<PackageKey, Edge> = BTreeMap::new();
+                for dep in index_ver.deps.iter() {
+                    if dep.kind == DependencyKind::Dev && !(is_root && dp.dev_deps) {
+                        continue;
+                    }
+                    if dep.optional
+                        && !is_root
+                        && !active_deps.contains(&(*name, ver.clone(), dep.name.as_str()))
+                    {
+                        continue;
+                    }
+                    let Some(target) = dp.dep_target(dep, *name, ver, map) else {
+                        continue;
+                    };
+                    deps.entry((dep.package_name, target)).or_default().add(
+                        kind_from_index(dep.kind),
+                        dep.features.iter().copied(),
+                        dep.default_features,
+                        dep.optional,
+                    );
+                }
+                edges.insert(key, deps);
+            }
+        }
+        Self {
+            root: root_key,
+            edges,
+        }
+    }
+
+    /// How each package other than the root can be reached from it.
+    pub fn reach(&self) -> BTreeMap<PackageKey, Reach> {
+        let mut reach: BTreeMap<PackageKey, Reach> = BTreeMap::new();
+        let mut stack = vec![(&self.root, None)];
+        while let Some((from, path)) = stack.pop() {
+            for (to, edge) in self.edges.get(from).into_iter().flatten() {
+                for kind in &edge.kinds {
+                    // The kind of the path so far, only the root's dev dependencies count.
+                    let next = match (path, kind) {
+                        (None, kind) => *kind,
+                        (Some(_), DepKind::Development) => continue,
+                        (Some(DepKind::Normal), kind) => *kind,
+                        (Some(path), _) => path,
+                    };
+                    let r = reach.entry(to.clone()).or_default();
+                    let seen = match next {
+                        DepKind::Normal => std::mem::replace(&mut r.normal, true),
+                        DepKind::Build => std::mem::replace(&mut r.build, true),
+                        DepKind::Development => std::mem::replace(&mut r.dev, true),
+                    };
+                    if !seen {
+                        stack.push((to, Some(next)));
+                    }
+                }
+            }
+        }
+        reach.remove(&self.root);
+        reach
+    }
+
+    pub fn footprint(&self) -> Footprint {
+        let mut footprint = Footprint::default();
+        for r in self.reach().values() {
+            if r.normal || r.build {
+                footprint.production += 1;
+            } else if r.dev {
+                footprint.dev_only += 1;
+            }
+            if r.build {
+                footprint.build += 1;
+            }
+        }
+        footprint
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph \"{}\" {{", node_id(&self.root)).unwrap();
+        writeln!(out, "    \"{}\" [shape=box];", node_id(&self.root)).unwrap();
+        for (from, deps) in &self.edges {
+            if deps.is_empty() && from != &self.root {
+                writeln!(out, "    \"{}\";", node_id(from)).unwrap();
+            }
+            for (to, edge) in deps {
+                let mut label: Vec<_> = edge.kinds.iter().map(|k| kind_name(*k)).collect();
+                if edge.optional {
+                    label.push("optional");
+                }
+                label.extend(edge.features.iter().map(|f| f.as_str()));
+                let style = if edge.kinds.contains(&DepKind::Normal) {
+                    "solid"
+                } else if edge.kinds.contains(&DepKind::Build) {
+                    "dashed"
+                } else {
+                    "dotted"
+                };
+                writeln!(
+                    out,
+                    "    \"{}\" -> \"{}\" [label=\"{}\", style={style}];",
+                    node_id(from),
+                    node_id(to),
+                    label.join(" "),
+                )
+                .unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Each node has the `Reach` of its package, which is `null` for the root.
+    pub fn to_json(&self) -> serde_json::Value {
+        let reach = self.reach();
+        let nodes: Vec<_> = self
+            .edges
+            .keys()
+            .map(|key| {
+                serde_json::json!({
+                    "id": node_id(key),
+                    "name": key.0.as_str(),
+                    "version": key.1.to_string(),
+                    "reach": reach.get(key),
+                })
+            })
+            .collect();
+        let edges: Vec<_> = self
+            .edges
+            .iter()
+            .flat_map(|(from, deps)| deps.iter().map(move |(to, edge)| (from, to, edge)))
+            .map(|(from, to, edge)| {
+                serde_json::json!({
+                    "from": node_id(from),
+                    "to": node_id(to),
+                    "kinds": edge.kinds.iter().map(|k| kind_name(*k)).collect::<Vec<_>>(),
+                    "features": edge.features.iter().map(|f| f.as_str()).collect::<Vec<_>>(),
+                    "default_features": edge.default_features,
+                    "optional": edge.optional,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "root": node_id(&self.root),
+            "nodes": nodes,
+            "edges": edges,
+        })
+    }
+
+    /// Writes `<name>.dot` and `<name>.json` to `dir`.
+    pub fn write_files(&self, dir: &Path, name: &str) -> anyhow::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(format!("{name}.dot")), self.to_dot())?;
+        let json = serde_json::to_string_pretty(&self.to_json())?;
+        std::fs::write(dir.join(format!("{name}.json")), json)?;
+        Ok(())
+    }
+}
+
+fn node_id((name, ver): &PackageKey) -> String {
+    format!("{name}@{ver}")
+}
+
+fn kind_name(kind: DepKind) -> &'static str {
+    match kind {
+        DepKind::Normal => "normal",
+        DepKind::Build => "build",
+        DepKind::Development => "dev",
+    }
+}
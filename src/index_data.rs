@@ -1,4 +1,7 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+};
 
 use cargo::util::interning::InternedString;
 use internment::Intern;
@@ -31,6 +34,11 @@ struct RawIndexDependency<'da> {
     #[serde(skip_serializing_if = "is_default")]
     #[serde(default)]
     optional: bool,
+    #[serde(skip_serializing_if = "is_default")]
+    #[serde(default)]
+    // A `cfg` often has quoted strings in it, which can not be borrowed once escaped.
+    #[serde(borrow)]
+    target: Option<Cow<'da, str>>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -43,6 +51,8 @@ pub struct Dependency {
     pub default_features: bool,
     pub kind: crates_index::DependencyKind,
     pub optional: bool,
+    /// The `cfg` or target triple this dependency is limited to, if any.
+    pub target: Option<InternedString>,
 }
 
 impl<'da> From<RawIndexDependency<'da>> for Dependency {
@@ -66,6 +76,7 @@ impl<'da> From<RawIndexDependency<'da>> for Dependency {
             default_features: value.default_features,
             kind: value.kind,
             optional: value.optional,
+            target: value.target.map(|s| InternedString::new(&s)),
         }
     }
 }
@@ -89,6 +100,7 @@ impl<'a> Into<RawIndexDependency<'static>> for &'a Dependency {
             default_features: self.default_features,
             kind: self.kind,
             optional: self.optional,
+            target: self.target.map(|s| s.as_str().into()),
         }
     }
 }
@@ -114,6 +126,7 @@ impl TryFrom<&crates_index::Dependency> for Dependency {
             kind: dep.kind(),
             optional: dep.is_optional(),
             default_features: dep.has_default_features(),
+            target: dep.target().map(|s| s.into()),
         })
     }
 }
@@ -171,6 +184,22 @@ fn is_default_semver_version_for_serde(t: &semver::Version) -> bool {
     t == &default_semver_version_for_serde()
 }
 
+pub fn checksum_to_hex(checksum: &[u8; 32]) -> String {
+    checksum.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Returns `None` for anything that is not 64 hex digits.
+pub fn checksum_from_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut checksum = [0; 32];
+    for (i, b) in checksum.iter_mut().enumerate() {
+        *b = u8::from_str_radix(hex.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+    Some(checksum)
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 struct RawIndexVersion<'da> {
     name: &'da str,
@@ -189,6 +218,14 @@ struct RawIndexVersion<'da> {
     #[serde(skip_serializing_if = "is_default")]
     #[serde(default)]
     yanked: bool,
+    #[serde(skip_serializing_if = "is_default")]
+    #[serde(default)]
+    #[serde(borrow)]
+    rust_version: Option<Cow<'da, str>>,
+    /// Hex encoded, as in the `Cargo.lock`.
+    #[serde(skip_serializing_if = "is_default")]
+    #[serde(default)]
+    checksum: Option<String>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -202,11 +239,13 @@ pub struct Version {
     pub features: Intern<BTreeMap<InternedString, Intern<BTreeSet<InternedString>>>>,
     pub links: Option<InternedString>,
     pub yanked: bool,
+    pub rust_version: Option<InternedString>,
+    /// The sha256 of the `.crate` file.
+    pub checksum: Option<[u8; 32]>,
 }
 
-#[cfg(test)]
 impl Version {
-    pub(crate) fn without_features(self) -> Option<Self> {
+    pub fn without_features(self) -> Option<Self> {
         if !self.features_raw.is_empty() {
             Some(Self {
                 features_raw: Intern::new(Default::default()),
@@ -217,7 +256,7 @@ impl Version {
             None
         }
     }
-    pub(crate) fn without_a_feature(self, i: usize) -> Option<Self> {
+    pub fn without_a_feature(self, i: usize) -> Option<Self> {
         if !self.features_raw.is_empty() {
             let features_raw: Intern<BTreeMap<InternedString, Intern<BTreeSet<InternedString>>>> =
                 Intern::new(
@@ -263,7 +302,7 @@ impl Version {
             None
         }
     }
-    pub(crate) fn without_deps(self) -> Option<Self> {
+    pub fn without_deps(self) -> Option<Self> {
         if !self.deps.deps.is_empty() {
             Some(Self {
                 deps: DependencyList::default(),
@@ -273,7 +312,28 @@ impl Version {
             None
         }
     }
-    pub(crate) fn without_a_dep(self, i: usize) -> Option<Self> {
+    pub fn without_dev_deps(self) -> Self {
+        Self {
+            deps: DependencyList {
+                deps: self
+                    .deps
+                    .deps
+                    .iter()
+                    .map(|(f, d)| {
+                        let d: Vec<_> = d
+                            .iter()
+                            .filter(|d| d.kind != crates_index::DependencyKind::Dev)
+                            .cloned()
+                            .collect();
+                        (f.clone(), Intern::new(d))
+                    })
+                    .filter(|(_, d)| !d.is_empty())
+                    .collect(),
+            },
+            ..self
+        }
+    }
+    pub fn without_a_dep(self, i: usize) -> Option<Self> {
         if !self.deps.deps.is_empty() {
             Some(Self {
                 deps: DependencyList {
@@ -340,6 +400,8 @@ impl<'da> From<RawIndexVersion<'da>> for Version {
             features: features.into(),
             links: value.links.map(|s| s.into()),
             yanked: value.yanked,
+            rust_version: value.rust_version.map(|s| InternedString::new(&s)),
+            checksum: value.checksum.as_deref().and_then(checksum_from_hex),
         }
     }
 }
@@ -357,6 +419,8 @@ impl Into<RawIndexVersion<'static>> for Version {
                 .collect(),
             links: self.links.map(|s| s.as_str()),
             yanked: self.yanked,
+            rust_version: self.rust_version.map(|s| s.as_str().into()),
+            checksum: self.checksum.as_ref().map(checksum_to_hex),
         }
     }
 }
@@ -427,6 +491,8 @@ impl TryFrom<&crates_index::Version> for Version {
             features: features.into(),
             links: ver.links().map(|s| s.into()),
             yanked: ver.is_yanked(),
+            rust_version: ver.rust_version().map(|s| s.into()),
+            checksum: Some(*ver.checksum()),
         })
     }
 }
@@ -1,35 +1,53 @@
 use std::{
-    cell::{Cell, RefCell},
+    cell::{Cell, Ref, RefCell},
     cmp::Reverse,
-    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet},
     error::Error,
     fs::File,
     hash::{Hash, Hasher},
     io::{BufWriter, Write},
     ops::Bound,
+    path::PathBuf,
     time::Instant,
 };
 
 use cargo::{core::Summary, util::interning::InternedString};
+use cargo_workspace::CargoWorkspace;
 use crates_index::DependencyKind;
 use either::Either;
+use graph::{DependencyGraph, Footprint};
 use hasher::StableHasher;
 use itertools::Itertools as _;
-use names::{new_bucket, new_links, new_wide, FeatureNamespace, Names};
+use memory::{CountingAlloc, MemoryMeasurement, MemoryUsage};
+use names::{new_bucket, new_links, new_wide, FeatureNamespace, NameId, NameInterner, Names};
+use phase_times::{Phase, PhaseTimer, PhaseTimes};
 use pubgrub::{
-    resolve, Dependencies, DependencyConstraints, DependencyProvider, PackageResolutionStatistics,
-    PubGrubError, SelectedDependencies, VersionSet,
+    Dependencies, DependencyConstraints, DependencyProvider, PackageResolutionStatistics,
+    PubGrubError, VersionSet,
 };
 use rc_semver_pubgrub::RcSemverPubgrub;
+pub use rc_semver_pubgrub::VERSION_SET_KIND;
 use ron::ser::PrettyConfig;
 use semver_pubgrub::{SemverCompatibility, SemverPubgrub};
 
 pub mod cargo_resolver;
+pub mod cargo_workspace;
+pub mod compare;
+pub mod feature_resolver;
+pub mod features;
+pub mod graph;
 pub mod hasher;
 pub mod index_data;
+pub mod lockfile;
+pub mod memory;
+pub mod minimize;
 pub mod names;
+pub mod offline;
+mod phase_times;
 mod rc_semver_pubgrub;
 pub mod read_index;
+pub mod registry_server;
+pub mod snapshot;
 #[cfg(test)]
 mod tests;
 
@@ -38,53 +56,202 @@ use read_index::read_test_file;
 
 #[cfg(not(target_env = "msvc"))]
 #[global_allocator]
-static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+static GLOBAL: CountingAlloc<tikv_jemallocator::Jemalloc> =
+    CountingAlloc(tikv_jemallocator::Jemalloc);
 
 #[cfg(target_env = "msvc")]
 #[global_allocator]
-static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+static GLOBAL: CountingAlloc<mimalloc::MiMalloc> = CountingAlloc(mimalloc::MiMalloc);
 
 const TIME_MAKE_FILE: f32 = 40.0;
 const TIME_CUT_OFF: f32 = TIME_MAKE_FILE * 4.0;
 
-type IndexMapLookup = HashMap<
+pub type IndexMapLookup = HashMap<
     InternedString,
     BTreeMap<semver::Version, (index_data::Version, Summary)>,
     rustc_hash::FxBuildHasher,
 >;
 
+/// The versions of each crate that a previous resolution picked, to lock a new one to.
+pub type PastResult = HashMap<InternedString, BTreeSet<semver::Version>, rustc_hash::FxBuildHasher>;
+
+fn pub_past_result(map: &Solution<'_>) -> PastResult {
+    let mut results = PastResult::default();
+    for (k, v) in map.iter() {
+        if k.is_real() {
+            results.entry(k.crate_()).or_default().insert(v.clone());
+        }
+    }
+    results
+}
+
+fn cargo_past_result(resolve: &cargo::core::Resolve) -> PastResult {
+    let mut results = PastResult::default();
+    for v in resolve.iter() {
+        results
+            .entry(v.name())
+            .or_default()
+            .insert(v.version().clone());
+    }
+    results
+}
+
+/// A pubgrub solution with the packages translated back from `NameId`s.
+type Solution<'c> = HashMap<Names<'c>, semver::Version, rustc_hash::FxBuildHasher>;
+
 #[derive(Clone)]
 pub struct Index<'c> {
     crates: &'c IndexMapLookup,
-    past_result:
-        Option<HashMap<InternedString, BTreeSet<semver::Version>, rustc_hash::FxBuildHasher>>,
+    past_result: Option<PastResult>,
+    /// Versions to try before any others, without ruling the others out.
+    preferred: PastResult,
     dependencies: RefCell<HashSet<(InternedString, semver::Version), rustc_hash::FxBuildHasher>>,
     pubgrub_dependencies: RefCell<HashSet<(Names<'c>, semver::Version), rustc_hash::FxBuildHasher>>,
+    names: RefCell<NameInterner<'c>>,
     start: Cell<Instant>,
     should_cancel_call_count: Cell<u64>,
+    phase_times: Cell<PhaseTimes>,
+    /// Results of `get_dependencies` that are kept between roots, if enabled.
+    dependency_cache: Option<RefCell<DependencyCache>>,
+    dependency_cache_hits: Cell<u64>,
+    /// Roots resolved since the cache was last cleared.
+    dependency_cache_roots: u32,
+    prioritization: Prioritization,
+    lock_policy: LockPolicy,
+    /// The fewest `get_dependencies` steps from the root to each package,
+    /// only tracked for `Prioritization::DependencyDepth`.
+    depths: RefCell<HashMap<NameId, u32, rustc_hash::FxBuildHasher>>,
+    /// Where `crates` came from, recorded in the files written by `make_index_ron_file`.
+    snapshot_header: snapshot::Header,
+    /// If set, cargo is also run through `generate-lockfile` on each root.
+    cargo_workspace: Option<CargoWorkspace>,
+    /// Write the feature sets of roots where the resolvers disagree on them to `out/features`.
+    dump_features: bool,
+    /// Whether the root's dev dependencies are resolved.
+    dev_deps: bool,
+    /// Rerun feature activation as feature resolver 2 does on each pubgrub solution.
+    emulate_feature_resolver: bool,
+    /// Leave out the `Links` packages, so that any number of versions can use a `links` value.
+    ignore_links: bool,
+    /// Rerun roots without a solution with `ignore_links`, to see if `links` is the reason.
+    find_links_conflicts: bool,
+    /// Where to write the dependency graphs of each root, if anywhere.
+    export_graph: Option<PathBuf>,
 }
 
+type DependencyCache = HashMap<
+    (NameId, semver::Version),
+    Result<DependencyConstraints<NameId, RcSemverPubgrub>, String>,
+    rustc_hash::FxBuildHasher,
+>;
+
+/// The dependency cache, and the interner it refers to, are cleared after this many roots.
+/// This bounds the memory of each thread, and what `pub_memory` counts of earlier roots.
+const DEPENDENCY_CACHE_ROOTS: u32 = 1000;
+
 impl<'c> Index<'c> {
     pub fn new(crates: &'c IndexMapLookup) -> Self {
         Self {
             crates,
             past_result: None,
+            preferred: Default::default(),
             pubgrub_dependencies: Default::default(),
             dependencies: Default::default(),
+            names: Default::default(),
             start: Cell::new(Instant::now()),
             should_cancel_call_count: Cell::new(0),
+            phase_times: Cell::new(PhaseTimes::default()),
+            dependency_cache: None,
+            dependency_cache_hits: Cell::new(0),
+            dependency_cache_roots: 0,
+            prioritization: Prioritization::Default,
+            lock_policy: LockPolicy::Strict,
+            depths: Default::default(),
+            snapshot_header: Default::default(),
+            cargo_workspace: None,
+            dump_features: false,
+            dev_deps: true,
+            emulate_feature_resolver: false,
+            ignore_links: false,
+            find_links_conflicts: false,
+            export_graph: None,
+        }
+    }
+
+    pub fn set_find_links_conflicts(&mut self, find_links_conflicts: bool) {
+        self.find_links_conflicts = find_links_conflicts;
+    }
+
+    pub fn set_export_graph(&mut self, dir: Option<PathBuf>) {
+        self.export_graph = dir;
+    }
+
+    pub fn set_emulate_feature_resolver(&mut self, emulate_feature_resolver: bool) {
+        self.emulate_feature_resolver = emulate_feature_resolver;
+    }
+
+    pub fn set_dev_deps(&mut self, dev_deps: bool) {
+        self.dev_deps = dev_deps;
+    }
+
+    pub fn set_dump_features(&mut self, dump_features: bool) {
+        self.dump_features = dump_features;
+    }
+
+    pub fn set_cargo_workspace(&mut self, cargo_workspace: CargoWorkspace) {
+        self.cargo_workspace = Some(cargo_workspace);
+    }
+
+    pub fn set_snapshot_header(&mut self, header: snapshot::Header) {
+        self.snapshot_header = header;
+    }
+
+    pub fn set_prioritization(&mut self, prioritization: Prioritization) {
+        self.prioritization = prioritization;
+    }
+
+    pub fn set_lock_policy(&mut self, lock_policy: LockPolicy) {
+        self.lock_policy = lock_policy;
+    }
+
+    /// Restricts the next resolution to `lock`, or only prefers it, depending on the `LockPolicy`.
+    fn lock_to(&mut self, lock: PastResult) {
+        match self.lock_policy {
+            LockPolicy::Strict => self.past_result = Some(lock),
+            LockPolicy::Prefer => self.preferred = lock,
         }
     }
 
+    /// Keep the results of `get_dependencies` between roots instead of recomputing them.
+    /// The cache is only used when there is no `past_result` restricting the versions.
+    pub fn set_dependency_cache(&mut self, enabled: bool) {
+        self.dependency_cache = enabled.then(Default::default);
+    }
+
     fn reset(&mut self) {
         self.past_result = None;
+        self.preferred.clear();
         self.dependencies.get_mut().clear();
         self.pubgrub_dependencies.get_mut().clear();
+        self.depths.get_mut().clear();
+        self.ignore_links = false;
+        // The cached dependencies refer to the interned ids, so they have to be kept together.
+        if self.dependency_cache.is_some() && self.dependency_cache_roots < DEPENDENCY_CACHE_ROOTS {
+            self.dependency_cache_roots += 1;
+        } else {
+            if let Some(cache) = &mut self.dependency_cache {
+                cache.get_mut().clear();
+            }
+            self.dependency_cache_roots = 1;
+            self.names.get_mut().clear();
+        }
         self.reset_time();
     }
 
     fn reset_time(&mut self) {
         *self.should_cancel_call_count.get_mut() = 0;
+        *self.phase_times.get_mut() = PhaseTimes::default();
+        *self.dependency_cache_hits.get_mut() = 0;
         *self.start.get_mut() = Instant::now();
     }
 
@@ -96,7 +263,39 @@ impl<'c> Index<'c> {
         self.should_cancel_call_count.get()
     }
 
-    #[cfg(test)]
+    fn phase_timer(&self, phase: Phase) -> PhaseTimer<'_> {
+        PhaseTimer::new(&self.phase_times, phase)
+    }
+
+    fn intern(&self, name: Names<'c>) -> NameId {
+        self.names.borrow_mut().intern(name)
+    }
+
+    /// The interner stays borrowed while the handle is alive, so it must be dropped before
+    /// anything new is interned.
+    fn name(&self, id: NameId) -> Ref<'_, Names<'c>> {
+        Ref::map(self.names.borrow(), |names| names.name(id))
+    }
+
+    /// Formats an error from `resolve` with the packages by name rather than by id.
+    fn describe_error(&self, e: &PubGrubError<Self>) -> String {
+        self.names.borrow().replace_ids(&format!("{e:?}"))
+    }
+
+    fn resolve(
+        &self,
+        root: Names<'c>,
+        ver: semver::Version,
+    ) -> Result<Solution<'c>, PubGrubError<Self>> {
+        let root = self.intern(root);
+        let map = pubgrub::resolve(self, root, ver)?;
+        let names = self.names.borrow();
+        Ok(map
+            .into_iter()
+            .map(|(id, ver)| (names.name(id).clone(), ver))
+            .collect())
+    }
+
     fn make_pubgrub_ron_file(&self) {
         let mut dependency_provider: BTreeMap<_, BTreeMap<_, Result<_, _>>> = BTreeMap::new();
         let deps = self
@@ -114,7 +313,7 @@ impl<'c> Index<'c> {
         };
 
         for (package, version) in &deps {
-            match self.get_dependencies(package, version) {
+            match self.compute_dependencies(package, version) {
                 Ok(Dependencies::Available(dependencies)) => {
                     dependency_provider
                         .entry(package.clone())
@@ -142,6 +341,46 @@ impl<'c> Index<'c> {
         file.flush().unwrap();
     }
 
+    /// The root of the last resolution, which asks for all features.
+    fn resolved_root(&self) -> (Names<'c>, semver::Version) {
+        let deps = self.pubgrub_dependencies.borrow();
+        let Some(root) = deps
+            .iter()
+            .find(|(name, _)| matches!(name, Names::Bucket(_, _, all) if *all))
+        else {
+            panic!("no root")
+        };
+        root.clone()
+    }
+
+    /// The same answers as `make_pubgrub_ron_file`, as a plain pubgrub provider.
+    fn make_offline_file(&self) -> offline::OfflineFile {
+        let (root, root_version) = self.resolved_root();
+        let mut provider = offline::Provider::new();
+        for (package, version) in self.pubgrub_dependencies.borrow().iter() {
+            if let Ok(Dependencies::Available(deps)) = self.compute_dependencies(package, version) {
+                provider.add_dependencies(
+                    package.to_string(),
+                    version.clone(),
+                    deps.into_iter()
+                        .map(|(dep, range)| (dep.to_string(), (*range.inner).clone())),
+                );
+            }
+        }
+        offline::OfflineFile {
+            root: root.to_string(),
+            version: root_version,
+            provider,
+        }
+    }
+
+    fn make_pubgrub_offline_file(&self) {
+        let (root, version) = self.resolved_root();
+        std::fs::create_dir_all("out/pubgrub_offline").unwrap();
+        let file_name = format!("out/pubgrub_offline/{}@{}.ron", root.crate_(), version);
+        offline::write_file(file_name, &self.make_offline_file()).unwrap();
+    }
+
     fn make_index_ron_data(&self) -> Vec<index_data::Version> {
         let deps = self.dependencies.borrow();
 
@@ -161,12 +400,17 @@ impl<'c> Index<'c> {
             .find(|(name, _)| matches!(name, Names::Bucket(_, _, all) if *all))
             .unwrap();
 
-        let out = self.make_index_ron_data();
+        let header = snapshot::Header {
+            root: Some(snapshot::Root {
+                name: name.0.crate_().to_string(),
+                version: name.1.clone(),
+            }),
+            ..self.snapshot_header.clone()
+        };
+        let out = snapshot::Snapshot::new(header, self.make_index_ron_data());
 
         let file_name = format!("out/index_ron/{}@{}.ron", name.0.crate_(), name.1);
-        let mut file = BufWriter::new(File::create(&file_name).unwrap());
-        ron::ser::to_writer_pretty(&mut file, &out, PrettyConfig::new()).unwrap();
-        file.flush().unwrap();
+        snapshot::write_file(file_name, &out).unwrap();
     }
 
     fn get_versions<Q>(&self, name: &Q) -> impl Iterator<Item = &semver::Version> + '_
@@ -194,6 +438,23 @@ impl<'c> Index<'c> {
         }
     }
 
+    /// The versions in `preferred` that `get_versions` would also return, newest first.
+    fn get_preferred_versions<Q>(&self, name: &Q) -> impl Iterator<Item = &semver::Version> + '_
+    where
+        Q: ?Sized + Hash + Eq,
+        InternedString: std::borrow::Borrow<Q>,
+    {
+        let data = self.crates.get(name);
+        let past = self.past_result.as_ref().map(|past| past.get(name));
+        self.preferred
+            .get(name)
+            .into_iter()
+            .flat_map(|m| m.iter())
+            .rev()
+            .filter(move |v| data.map_or(false, |d| d.contains_key(v)))
+            .filter(move |v| past.map_or(true, |p| p.map_or(false, |p| p.contains(v))))
+    }
+
     fn get_version<Q>(&self, name: &Q, ver: &semver::Version) -> Option<&'c index_data::Version>
     where
         Q: ?Sized + Hash + Eq,
@@ -243,6 +504,20 @@ impl<'c> Index<'c> {
             .count() as u32
     }
 
+    fn count_versions(&self, package: &Names<'c>, range: &RcSemverPubgrub) -> u32 {
+        match package {
+            Names::Links(_) => u32::MAX,
+            Names::Wide(_, req, _, _)
+            | Names::WideFeatures(_, req, _, _, _)
+            | Names::WideDefaultFeatures(_, req, _, _) => {
+                self.count_wide_matches(range, &package.crate_(), req)
+            }
+            Names::Bucket(..) | Names::BucketFeatures(..) | Names::BucketDefaultFeatures(..) => {
+                self.count_matches(range, &package.crate_())
+            }
+        }
+    }
+
     fn count_matches<Q>(&self, range: &RcSemverPubgrub, package: &Q) -> u32
     where
         Q: ?Sized + Hash + Eq,
@@ -276,8 +551,75 @@ impl<'c> Index<'c> {
         }
     }
 
+    /// The version in `map` that `dep`, a dependency of `from@from_ver`, was resolved to.
+    /// A wide requirement can match more than one selected version, so this follows the
+    /// package pubgrub used for the dependency instead of matching the requirement again.
+    pub(crate) fn dep_target(
+        &self,
+        dep: &'c index_data::Dependency,
+        from: InternedString,
+        from_ver: &semver::Version,
+        map: &Solution<'c>,
+    ) -> Option<semver::Version> {
+        let (cray, _) = self.from_dep(dep, from, from_ver);
+        let compat = match cray {
+            Names::Bucket(_, compat, _) => compat,
+            // The solution of a wide package is the compatibility range that was picked.
+            _ => map.get(&cray)?.into(),
+        };
+        map.get(&new_bucket(dep.package_name, compat, false))
+            .cloned()
+    }
+
+    /// For a root without a solution, looks for a solution that ignores `links`.
+    /// If there is one, the root only fails because of `links`, and the solution has
+    /// two versions that use the same value.
+    ///
+    /// This restarts the clock. What was recorded of the packages looked at is kept as it was
+    /// for the original resolution, so files written afterwards are not mixed with the rerun.
+    fn find_links_conflict(
+        &mut self,
+        root: Names<'c>,
+        ver: &semver::Version,
+    ) -> Option<LinksConflict> {
+        let dependencies = self.dependencies.take();
+        let pubgrub_dependencies = self.pubgrub_dependencies.take();
+        let depths = self.depths.take();
+        self.ignore_links = true;
+        self.reset_time();
+        let res = self.resolve(root, ver.clone());
+        self.ignore_links = false;
+        *self.dependencies.get_mut() = dependencies;
+        *self.pubgrub_dependencies.get_mut() = pubgrub_dependencies;
+        *self.depths.get_mut() = depths;
+        let map = res.ok()?;
+        let mut users: HashMap<InternedString, (InternedString, semver::Version)> = HashMap::new();
+        for (name, ver) in &map {
+            let Names::Bucket(name, _, _) = name else {
+                continue;
+            };
+            let Some(links) = self.get_version(name.as_str(), ver).and_then(|v| v.links) else {
+                continue;
+            };
+            match users.entry(links) {
+                Entry::Occupied(first) if first.get() != &(*name, ver.clone()) => {
+                    return Some(LinksConflict {
+                        links,
+                        first: first.get().clone(),
+                        second: (*name, ver.clone()),
+                    });
+                }
+                Entry::Occupied(_) => {}
+                Entry::Vacant(e) => {
+                    e.insert((*name, ver.clone()));
+                }
+            }
+        }
+        None
+    }
+
     #[must_use]
-    fn check_cycles(&self, root: Names<'c>, pubmap: &SelectedDependencies<Self>) -> bool {
+    fn check_cycles(&self, root: Names<'c>, pubmap: &Solution<'c>) -> bool {
         let mut vertions: HashMap<
             (InternedString, SemverCompatibility, bool),
             (semver::Version, BTreeSet<_>, BTreeSet<_>),
@@ -336,7 +678,7 @@ impl<'c> Index<'c> {
     fn visit(
         &self,
         id: (InternedString, SemverCompatibility, bool),
-        pubmap: &SelectedDependencies<Self>,
+        pubmap: &Solution<'c>,
         vertions: &HashMap<
             (InternedString, SemverCompatibility, bool),
             (semver::Version, BTreeSet<&str>, BTreeSet<&str>),
@@ -378,13 +720,14 @@ impl<'c> Index<'c> {
     }
 
     #[must_use]
-    fn check(&self, root: Names, pubmap: &SelectedDependencies<Self>) -> bool {
+    fn check(&self, root: Names<'c>, pubmap: &Solution<'c>) -> bool {
         // Basic dependency resolution properties
         if !pubmap.contains_key(&root) {
             return false;
         }
         for (name, ver) in pubmap {
-            let Dependencies::Available(deps) = self.get_dependencies(name, ver).unwrap() else {
+            let Dependencies::Available(deps) = self.compute_dependencies(name, ver).unwrap()
+            else {
                 return false;
             };
             for (dep, req) in deps {
@@ -533,7 +876,7 @@ fn deps_insert<'c>(
 }
 
 impl<'c> DependencyProvider for Index<'c> {
-    type P = Names<'c>;
+    type P = NameId;
 
     type V = semver::Version;
 
@@ -543,9 +886,11 @@ impl<'c> DependencyProvider for Index<'c> {
     type Err = SomeError;
     fn choose_version(
         &self,
-        package: &Names,
+        package: &NameId,
         range: &RcSemverPubgrub,
     ) -> Result<Option<semver::Version>, Self::Err> {
+        let _timer = self.phase_timer(Phase::ChooseVersion);
+        let package = &*self.name(*package);
         Ok(match package {
             Names::Links(_name) => {
                 let Some((_, Bound::Included(v))) = range.inner.bounding_range() else {
@@ -558,7 +903,8 @@ impl<'c> DependencyProvider for Index<'c> {
             | Names::WideFeatures(_, req, _, _, _)
             | Names::WideDefaultFeatures(_, req, _, _) => {
                 // one version for each bucket that match req
-                self.get_versions(&*package.crate_())
+                self.get_preferred_versions(&*package.crate_())
+                    .chain(self.get_versions(&*package.crate_()))
                     .filter(|v| req.matches(v))
                     .map(|v| SemverCompatibility::from(v))
                     .map(|v| v.canonical())
@@ -567,7 +913,8 @@ impl<'c> DependencyProvider for Index<'c> {
             Names::Bucket(_, _, _)
             | Names::BucketFeatures(_, _, _)
             | Names::BucketDefaultFeatures(_, _) => self
-                .get_versions(&*package.crate_())
+                .get_preferred_versions(&*package.crate_())
+                .chain(self.get_versions(&*package.crate_()))
                 .find(|v| range.contains(v))
                 .cloned(),
         })
@@ -577,10 +924,24 @@ impl<'c> DependencyProvider for Index<'c> {
 
     fn prioritize(
         &self,
-        package: &Names<'c>,
+        package: &NameId,
         range: &RcSemverPubgrub,
         stats: &PackageResolutionStatistics,
     ) -> Self::Priority {
+        let _timer = self.phase_timer(Phase::Prioritize);
+        let id = *package;
+        let package = &*self.name(id);
+        match self.prioritization {
+            Prioritization::Default => {}
+            Prioritization::FewestVersions => {
+                return (0, Reverse(self.count_versions(package, range)));
+            }
+            Prioritization::ConflictOnly => return (stats.conflict_count(), Reverse(0)),
+            Prioritization::DependencyDepth => {
+                let depth = self.depths.borrow().get(&id).copied().unwrap_or(0);
+                return (stats.conflict_count(), Reverse(depth));
+            }
+        }
         match package {
             Names::Links(_name) => {
                 // PubGrub automatically handles when any requirement has no overlap. So this is only deciding a importance of picking the version:
@@ -650,12 +1011,118 @@ impl<'c> DependencyProvider for Index<'c> {
 
     fn get_dependencies(
         &self,
-        package: &Names<'c>,
+        id: &NameId,
         version: &semver::Version,
     ) -> Result<Dependencies<Self::P, Self::VS, Self::M>, Self::Err> {
+        // Cloned, as working out the dependencies interns new names.
+        let package = &self.name(*id).clone();
+        let _timer = self.phase_timer(match package {
+            Names::Bucket(..) => Phase::Bucket,
+            Names::BucketFeatures(..) => Phase::BucketFeatures,
+            Names::BucketDefaultFeatures(..) => Phase::BucketDefaultFeatures,
+            Names::Wide(..) => Phase::Wide,
+            Names::WideFeatures(..) => Phase::WideFeatures,
+            Names::WideDefaultFeatures(..) => Phase::WideDefaultFeatures,
+            Names::Links(..) => Phase::Links,
+        });
         self.pubgrub_dependencies
             .borrow_mut()
             .insert((package.clone(), version.clone()));
+        let out = self.cached_dependencies(*id, package, version)?;
+        if self.prioritization == Prioritization::DependencyDepth {
+            self.record_depths(*id, &out);
+        }
+        Ok(out)
+    }
+
+    fn should_cancel(&self) -> Result<(), Self::Err> {
+        let calls = self.should_cancel_call_count.get();
+        self.should_cancel_call_count.set(calls + 1);
+        if calls % 64 == 0 && TIME_CUT_OFF < self.start.get().elapsed().as_secs_f32() {
+            return Err(SomeError);
+        }
+        Ok(())
+    }
+}
+
+impl<'c> Index<'c> {
+    fn cached_dependencies(
+        &self,
+        id: NameId,
+        package: &Names<'c>,
+        version: &semver::Version,
+    ) -> Result<Dependencies<NameId, RcSemverPubgrub, String>, SomeError> {
+        let Some(cache) = self
+            .dependency_cache
+            .as_ref()
+            .filter(|_| self.past_result.is_none() && !self.ignore_links)
+        else {
+            return Ok(self.intern_dependencies(self.compute_dependencies(package, version)?));
+        };
+        let key = (id, version.clone());
+        let cached = cache.borrow().get(&key).cloned();
+        if let Some(cached) = cached {
+            self.dependency_cache_hits
+                .set(self.dependency_cache_hits.get() + 1);
+            // Keep track of the crate versions looked at, as `compute_dependencies` would have.
+            if let Names::Bucket(name, _, _)
+            | Names::BucketFeatures(name, _, FeatureNamespace::Feat(_))
+            | Names::BucketDefaultFeatures(name, _) = package
+            {
+                self.dependencies
+                    .borrow_mut()
+                    .insert((*name, version.clone()));
+            }
+            return Ok(match cached {
+                Ok(deps) => Dependencies::Available(deps),
+                Err(reason) => Dependencies::Unavailable(reason),
+            });
+        }
+        let out = self.intern_dependencies(self.compute_dependencies(package, version)?);
+        cache.borrow_mut().insert(
+            key,
+            match &out {
+                Dependencies::Available(deps) => Ok(deps.clone()),
+                Dependencies::Unavailable(reason) => Err(reason.clone()),
+            },
+        );
+        Ok(out)
+    }
+
+    /// Dependencies are one step further from the root than the package that asked for them.
+    fn record_depths(&self, id: NameId, deps: &Dependencies<NameId, RcSemverPubgrub, String>) {
+        let Dependencies::Available(deps) = deps else {
+            return;
+        };
+        let mut depths = self.depths.borrow_mut();
+        let depth = depths.get(&id).copied().unwrap_or(0) + 1;
+        for dep in deps.keys() {
+            depths
+                .entry(*dep)
+                .and_modify(|d| *d = (*d).min(depth))
+                .or_insert(depth);
+        }
+    }
+
+    fn intern_dependencies(
+        &self,
+        deps: Dependencies<Names<'c>, RcSemverPubgrub, String>,
+    ) -> Dependencies<NameId, RcSemverPubgrub, String> {
+        match deps {
+            Dependencies::Available(deps) => Dependencies::Available(
+                deps.into_iter()
+                    .map(|(name, range)| (self.intern(name), range))
+                    .collect(),
+            ),
+            Dependencies::Unavailable(reason) => Dependencies::Unavailable(reason),
+        }
+    }
+
+    fn compute_dependencies(
+        &self,
+        package: &Names<'c>,
+        version: &semver::Version,
+    ) -> Result<Dependencies<Names<'c>, RcSemverPubgrub, String>, SomeError> {
         Ok(match package {
             &Names::Bucket(name, _major, all_features) => {
                 let Some(index_ver) = self.get_version(name.as_str(), version) else {
@@ -668,7 +1135,7 @@ impl<'c> DependencyProvider for Index<'c> {
                     return Ok(Dependencies::Unavailable("yanked: Bucket".into()));
                 }
                 let mut deps = DependencyConstraints::default();
-                if let Some(link) = &index_ver.links {
+                if let Some(link) = index_ver.links.as_ref().filter(|_| !self.ignore_links) {
                     let index_unique_to_each_crate_version = {
                         let mut state = StableHasher::new();
                         package.hash(&mut state);
@@ -679,7 +1146,7 @@ impl<'c> DependencyProvider for Index<'c> {
                     deps.insert(new_links(*link), RcSemverPubgrub::singleton(ver));
                 }
                 for dep in index_ver.deps.iter() {
-                    if dep.kind == DependencyKind::Dev && !all_features {
+                    if dep.kind == DependencyKind::Dev && !(all_features && self.dev_deps) {
                         continue;
                     }
                     if dep.optional && !all_features {
@@ -707,6 +1174,9 @@ impl<'c> DependencyProvider for Index<'c> {
                             if let Some((dep, dep_feat)) = val.split_once('/') {
                                 let dep_name = dep.strip_suffix('?').unwrap_or(dep);
                                 for com in index_ver.deps.get(dep_name) {
+                                    if com.kind == DependencyKind::Dev && !self.dev_deps {
+                                        continue;
+                                    }
                                     let (cray, req_range) = self.from_dep(com, name, version);
                                     deps_insert(
                                         &mut deps,
@@ -912,15 +1382,6 @@ impl<'c> DependencyProvider for Index<'c> {
             Names::Links(_) => Dependencies::Available(DependencyConstraints::default()),
         })
     }
-
-    fn should_cancel(&self) -> Result<(), Self::Err> {
-        let calls = self.should_cancel_call_count.get();
-        self.should_cancel_call_count.set(calls + 1);
-        if calls % 64 == 0 && TIME_CUT_OFF < self.start.get().elapsed().as_secs_f32() {
-            return Err(SomeError);
-        }
-        Ok(())
-    }
 }
 
 #[derive(clap::ValueEnum, Clone, Debug, Copy)]
@@ -932,6 +1393,32 @@ pub enum Mode {
     CargoLock,
 }
 
+/// The heuristic `Index::prioritize` uses to pick which package pubgrub decides next.
+#[derive(clap::ValueEnum, serde::Serialize, Clone, Debug, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Prioritization {
+    /// Most conflicts first, then fewest matching versions,
+    /// with special cases for each kind of package.
+    Default,
+    /// Fewest matching versions first, ignoring conflicts.
+    FewestVersions,
+    /// Most conflicts first, leaving ties to pubgrub.
+    ConflictOnly,
+    /// Most conflicts first, then the packages closest to the root.
+    DependencyDepth,
+}
+
+/// How a resolution that is given a previous result treats it.
+#[derive(clap::ValueEnum, serde::Serialize, Clone, Debug, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LockPolicy {
+    /// Only the locked versions can be picked.
+    Strict,
+    /// The locked versions are tried first, but any other version can be picked,
+    /// like cargo does with `VersionPreferences::prefer_package_id`.
+    Prefer,
+}
+
 impl Mode {
     fn build_pub(&self) -> bool {
         match self {
@@ -979,6 +1466,17 @@ pub fn process_crate_version(
     crt: InternedString,
     ver: semver::Version,
     mode: Mode,
+) -> OutputSummary {
+    run_crate_version(dp, crt, ver, mode, true)
+}
+
+/// Without `report` nothing is written or printed, for the repeats that only measure.
+fn run_crate_version(
+    dp: &mut Index,
+    crt: InternedString,
+    ver: semver::Version,
+    mode: Mode,
+    report: bool,
 ) -> OutputSummary {
     let root = new_bucket(crt, (&ver).into(), true);
     dp.reset();
@@ -988,8 +1486,19 @@ pub fn process_crate_version(
     let mut pub_time = 0.0;
     let mut should_cancel_call_count = 0;
     let mut get_dependencies_call_count = 0;
+    let mut phase_times = PhaseTimes::default();
+    let mut solver_time = 0.0;
+    let mut pub_memory = MemoryUsage::default();
+    let mut dependency_cache_hits = 0;
+    let mut links_conflict = None;
+    let mut links_conflict_time = 0.0;
     if mode.build_pub() {
-        res = Some(resolve(dp, root.clone(), (&ver).clone()));
+        let measurement = MemoryMeasurement::start();
+        res = Some(dp.resolve(root.clone(), (&ver).clone()));
+        pub_memory = measurement.finish();
+        phase_times = dp.phase_times.get();
+        dependency_cache_hits = dp.dependency_cache_hits.get();
+        solver_time = dp.duration() - phase_times.provider().as_secs_f32();
         cyclic_package_dependency = if let Some(Ok(map)) = res.as_ref() {
             dp.check_cycles(root.clone(), map)
         } else {
@@ -1002,25 +1511,38 @@ pub fn process_crate_version(
         match res.as_ref().unwrap().as_ref() {
             Ok(map) => {
                 if !dp.check(root.clone(), &map) {
-                    dp.make_index_ron_file();
+                    if report {
+                        dp.make_index_ron_file();
+                    }
                     panic!("failed check");
                 }
             }
             Err(PubGrubError::NoSolution(_derivation)) => {}
             Err(e) => {
-                dp.make_index_ron_file();
-                dbg!(e);
+                if report {
+                    dp.make_index_ron_file();
+                    eprintln!("pubgrub failed on {root:?}: {}", dp.describe_error(e));
+                }
             }
         }
-        if pub_time > TIME_MAKE_FILE {
+        if report && pub_time > TIME_MAKE_FILE {
             dp.make_index_ron_file();
         }
+        if dp.find_links_conflicts
+            && matches!(res.as_ref().unwrap(), Err(PubGrubError::NoSolution(_)))
+        {
+            links_conflict = dp.find_links_conflict(root.clone(), &ver);
+            links_conflict_time = dp.duration();
+        }
     }
     let mut cargo_out = None;
     let mut cargo_time = 0.0;
+    let mut cargo_memory = MemoryUsage::default();
     if mode.build_cargo() {
         dp.reset_time();
+        let measurement = MemoryMeasurement::start();
         cargo_out = Some(cargo_resolver::resolve(crt, &ver, dp));
+        cargo_memory = measurement.finish();
         cargo_time = dp.duration();
         cyclic_package_dependency = &cargo_out
             .as_ref()
@@ -1029,12 +1551,13 @@ pub fn process_crate_version(
             .map_err(|e| e.to_string().starts_with("cyclic package dependency"))
             == &Err(true);
         if let Some(pub_cyclic_package_dependency) = pub_cyclic_package_dependency {
-            if cyclic_package_dependency != pub_cyclic_package_dependency {
+            if report && cyclic_package_dependency != pub_cyclic_package_dependency {
                 dp.make_index_ron_file();
                 println!("failed to cyclic_package_dependency {root:?}");
             }
 
-            if !cyclic_package_dependency
+            if report
+                && !cyclic_package_dependency
                 && res.as_ref().unwrap().is_ok() != cargo_out.as_ref().unwrap().is_ok()
             {
                 dp.make_index_ron_file();
@@ -1042,26 +1565,106 @@ pub fn process_crate_version(
             }
         }
     }
-    let mut cargo_check_pub_lock_time = 0.0;
-    if mode.build_cargo_lock() && res.as_ref().unwrap().is_ok() {
-        dp.past_result = res
-            .as_ref()
-            .unwrap()
+    let pub_graph = match &res {
+        Some(Ok(map)) => Some(DependencyGraph::from_pubgrub(dp, &root, map)),
+        _ => None,
+    };
+    let cargo_graph = match &cargo_out {
+        Some(Ok(resolve)) => Some(DependencyGraph::from_cargo(resolve)),
+        _ => None,
+    };
+    if let Some(dir) = dp.export_graph.as_ref().filter(|_| report) {
+        if let Some(graph) = &pub_graph {
+            graph
+                .write_files(dir, &format!("{crt}@{ver}.pubgrub"))
+                .unwrap();
+        }
+        if let Some(graph) = &cargo_graph {
+            graph
+                .write_files(dir, &format!("{crt}@{ver}.cargo"))
+                .unwrap();
+        }
+    }
+    let pub_footprint = pub_graph.map_or_else(Footprint::default, |g| g.footprint());
+    let cargo_footprint = cargo_graph.map_or_else(Footprint::default, |g| g.footprint());
+
+    let mut feature_sets_differ = false;
+    if let (Some(Ok(map)), Some(Ok(resolve))) = (&res, &cargo_out) {
+        let differences = features::differences(
+            &features::pub_feature_sets(map),
+            &features::cargo_feature_sets(resolve),
+        );
+        feature_sets_differ = !differences.is_empty();
+        if report && feature_sets_differ && dp.dump_features {
+            features::write_differences_file(crt, &ver, &differences);
+        }
+    }
+
+    let pub_split_features = match &res {
+        Some(Ok(map)) if dp.emulate_feature_resolver => {
+            Some(feature_resolver::emulate_v2(dp, &root, map))
+        }
+        _ => None,
+    };
+    let pub_host_packages = pub_split_features.as_ref().map_or(0, |sets| {
+        sets.keys().filter(|(_, for_host)| *for_host).count()
+    });
+    let mut feature_resolver_differences = None;
+
+    let mut cargo_workspace_time = 0.0;
+    let mut cargo_workspace_deps = 0;
+    let mut cargo_workspace_diverged = false;
+    if let (Some(workspace), Some(cargo_out)) = (dp.cargo_workspace.clone(), &cargo_out) {
+        dp.reset_time();
+        let workspace_out = workspace.generate_lockfile(dp.crates, crt, &ver, dp.dev_deps);
+        cargo_workspace_time = dp.duration();
+        cargo_workspace_deps = workspace_out
             .as_ref()
-            .map(|map| {
-                let mut results: HashMap<
-                    InternedString,
-                    BTreeSet<semver::Version>,
-                    rustc_hash::FxBuildHasher,
-                > = HashMap::default();
-                for (k, v) in map.iter() {
-                    if k.is_real() {
-                        results.entry(k.crate_()).or_default().insert(v.clone());
+            .map_or(0, |r| cargo_resolver::registry_packages(r).len());
+        cargo_workspace_diverged = match (cargo_out, &workspace_out) {
+            (Ok(direct), Ok(workspace)) => {
+                cargo_resolver::registry_packages(direct)
+                    != cargo_resolver::registry_packages(workspace)
+            }
+            (Err(_), Err(_)) => false,
+            _ => true,
+        };
+        if report && cargo_workspace_diverged {
+            dp.make_index_ron_file();
+            println!("failed to match cargo workspace {root:?}");
+            if let Err(e) = &workspace_out {
+                println!("{e:?}");
+            }
+        }
+
+        // Only compared when cargo picked the same versions, so that the features are all
+        // that can differ.
+        if let (Some(Ok(map)), Some(pub_sets), Ok(workspace_resolve)) =
+            (&res, &pub_split_features, &workspace_out)
+        {
+            if pub_past_result(map) == cargo_past_result(workspace_resolve) {
+                match workspace.resolve_features(dp.dev_deps) {
+                    Ok(cargo_sets) => {
+                        let differences =
+                            feature_resolver::count_differences(pub_sets, &cargo_sets);
+                        if report && differences > 0 {
+                            dp.make_index_ron_file();
+                            println!("failed to match cargo feature resolver {root:?}");
+                        }
+                        feature_resolver_differences = Some(differences);
                     }
+                    Err(e) if report => {
+                        println!("cargo feature resolver failed on {root:?}: {e:?}")
+                    }
+                    Err(_) => {}
                 }
-                results
-            })
-            .ok();
+            }
+        }
+    }
+
+    let mut cargo_check_pub_lock_time = 0.0;
+    if mode.build_cargo_lock() && res.as_ref().unwrap().is_ok() {
+        dp.lock_to(pub_past_result(res.as_ref().unwrap().as_ref().unwrap()));
         dp.reset_time();
         let cargo_check_pub_lock_out = cargo_resolver::resolve(crt, &ver, dp);
         cargo_check_pub_lock_time = dp.duration();
@@ -1071,7 +1674,7 @@ pub fn process_crate_version(
             .map_err(|e| e.to_string().starts_with("cyclic package dependency"))
             == &Err(true);
 
-        if !cyclic_package_dependency_pub_lock && !cargo_check_pub_lock_out.is_ok() {
+        if report && !cyclic_package_dependency_pub_lock && !cargo_check_pub_lock_out.is_ok() {
             dp.make_index_ron_file();
             println!("failed to match pub lock cargo {root:?}");
         }
@@ -1079,30 +1682,14 @@ pub fn process_crate_version(
 
     let mut pub_check_cargo_lock_time = 0.0;
     if mode.build_pub_lock() && cargo_out.as_ref().unwrap().is_ok() {
-        dp.past_result = cargo_out
-            .as_ref()
-            .unwrap()
-            .as_ref()
-            .map(|map| {
-                let mut results: HashMap<
-                    InternedString,
-                    BTreeSet<semver::Version>,
-                    rustc_hash::FxBuildHasher,
-                > = HashMap::default();
-                for v in map.iter() {
-                    results
-                        .entry(v.name())
-                        .or_default()
-                        .insert(v.version().clone());
-                }
-                results
-            })
-            .ok();
+        dp.lock_to(cargo_past_result(
+            cargo_out.as_ref().unwrap().as_ref().unwrap(),
+        ));
         dp.reset_time();
-        let pub_check_cargo_lock_out = resolve(dp, root.clone(), ver.clone());
+        let pub_check_cargo_lock_out = dp.resolve(root.clone(), ver.clone());
         pub_check_cargo_lock_time = dp.duration();
 
-        if !pub_check_cargo_lock_out.is_ok() {
+        if report && !pub_check_cargo_lock_out.is_ok() {
             dp.make_index_ron_file();
             println!("failed to match cargo lock pub {root:?}");
         }
@@ -1130,32 +1717,183 @@ pub fn process_crate_version(
         name: crt,
         ver,
         time: pub_time,
+        time_min: pub_time,
+        time_max: pub_time,
         succeeded: matches!(&res, Some(Ok(_))),
         should_cancel_call_count,
         get_dependencies_call_count,
+        dependency_cache_hits,
+        prioritization: dp.prioritization,
+        lock_policy: dp.lock_policy,
+        dev_deps: dp.dev_deps,
+        choose_version_time: phase_times.choose_version.as_secs_f32(),
+        prioritize_time: phase_times.prioritize.as_secs_f32(),
+        get_dependencies_bucket_time: phase_times.bucket.as_secs_f32(),
+        get_dependencies_bucket_features_time: phase_times.bucket_features.as_secs_f32(),
+        get_dependencies_bucket_default_features_time: phase_times
+            .bucket_default_features
+            .as_secs_f32(),
+        get_dependencies_wide_time: phase_times.wide.as_secs_f32(),
+        get_dependencies_wide_features_time: phase_times.wide_features.as_secs_f32(),
+        get_dependencies_wide_default_features_time: phase_times
+            .wide_default_features
+            .as_secs_f32(),
+        get_dependencies_links_time: phase_times.links.as_secs_f32(),
+        solver_time,
+        pub_peak_bytes: pub_memory.peak_bytes,
+        pub_allocated_bytes: pub_memory.allocated_bytes,
+        dependencies_len: dp.dependencies.borrow().len(),
         pubgrub_deps,
         deps,
         cargo_time,
+        cargo_time_min: cargo_time,
+        cargo_time_max: cargo_time,
         cyclic_package_dependency,
         cargo_deps,
+        pub_production_deps: pub_footprint.production,
+        pub_build_deps: pub_footprint.build,
+        pub_dev_only_deps: pub_footprint.dev_only,
+        cargo_production_deps: cargo_footprint.production,
+        cargo_build_deps: cargo_footprint.build,
+        cargo_dev_only_deps: cargo_footprint.dev_only,
+        cargo_peak_bytes: cargo_memory.peak_bytes,
+        cargo_allocated_bytes: cargo_memory.allocated_bytes,
+        feature_sets_differ,
+        cargo_workspace_time,
+        cargo_workspace_deps,
+        cargo_workspace_diverged,
+        pub_host_packages,
+        feature_resolver_differences,
+        links_conflict: links_conflict.map(|c| c.to_string()),
+        links_conflict_time,
         cargo_check_pub_lock_time,
         pub_check_cargo_lock_time,
     }
 }
 
+/// Process the same root `repeat` times, after one discarded warmup run.
+///
+/// The returned row is the run with the median PubGrub time, with the cargo time replaced by
+/// the median cargo time and the min/max columns filled in from all the runs.
+pub fn process_crate_version_repeated(
+    dp: &mut Index,
+    crt: InternedString,
+    ver: semver::Version,
+    mode: Mode,
+    repeat: usize,
+) -> OutputSummary {
+    if repeat <= 1 {
+        return process_crate_version(dp, crt, ver, mode);
+    }
+    // The warm up run is the one that reports, the repeats only measure.
+    process_crate_version(dp, crt, ver.clone(), mode);
+    let mut runs = (0..repeat)
+        .map(|_| run_crate_version(dp, crt, ver.clone(), mode, false))
+        .sorted_by(|a, b| a.time.total_cmp(&b.time))
+        .collect_vec();
+    let cargo_times = runs
+        .iter()
+        .map(|r| r.cargo_time)
+        .sorted_by(f32::total_cmp)
+        .collect_vec();
+    let time_min = runs[0].time;
+    let time_max = runs[runs.len() - 1].time;
+    let mut out = runs.swap_remove(runs.len() / 2);
+    out.time_min = time_min;
+    out.time_max = time_max;
+    out.cargo_time = cargo_times[cargo_times.len() / 2];
+    out.cargo_time_min = cargo_times[0];
+    out.cargo_time_max = cargo_times[cargo_times.len() - 1];
+    out
+}
+
+/// Two versions in a solution that use the same `links` value, which cargo does not allow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinksConflict {
+    pub links: InternedString,
+    pub first: (InternedString, semver::Version),
+    pub second: (InternedString, semver::Version),
+}
+
+impl std::fmt::Display for LinksConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}@{} and {}@{} both link {}",
+            self.first.0, self.first.1, self.second.0, self.second.1, self.links
+        )
+    }
+}
+
 #[derive(serde::Serialize)]
 pub struct OutputSummary {
     pub name: InternedString,
     pub ver: semver::Version,
     pub time: f32,
+    pub time_min: f32,
+    pub time_max: f32,
     pub succeeded: bool,
     pub should_cancel_call_count: u64,
     pub get_dependencies_call_count: usize,
+    pub dependency_cache_hits: u64,
+    pub prioritization: Prioritization,
+    pub lock_policy: LockPolicy,
+    pub dev_deps: bool,
+    pub choose_version_time: f32,
+    pub prioritize_time: f32,
+    pub get_dependencies_bucket_time: f32,
+    pub get_dependencies_bucket_features_time: f32,
+    pub get_dependencies_bucket_default_features_time: f32,
+    pub get_dependencies_wide_time: f32,
+    pub get_dependencies_wide_features_time: f32,
+    pub get_dependencies_wide_default_features_time: f32,
+    pub get_dependencies_links_time: f32,
+    /// Time in `resolve` that was not spent in any of the provider callbacks.
+    pub solver_time: f32,
+    pub pub_peak_bytes: usize,
+    pub pub_allocated_bytes: usize,
+    /// Distinct crate versions looked at by all the resolutions of this root.
+    pub dependencies_len: usize,
     pub pubgrub_deps: usize,
     pub deps: usize,
     pub cargo_time: f32,
+    pub cargo_time_min: f32,
+    pub cargo_time_max: f32,
     pub cyclic_package_dependency: bool,
     pub cargo_deps: usize,
+    /// Packages needed to build the root, through normal or build dependencies.
+    pub pub_production_deps: usize,
+    /// Of those, the packages built for the host because a build dependency leads to them.
+    pub pub_build_deps: usize,
+    /// Packages only reached through the root's dev dependencies.
+    pub pub_dev_only_deps: usize,
+    /// `pub_production_deps` for cargo's resolution.
+    pub cargo_production_deps: usize,
+    /// `pub_build_deps` for cargo's resolution.
+    pub cargo_build_deps: usize,
+    /// `pub_dev_only_deps` for cargo's resolution.
+    pub cargo_dev_only_deps: usize,
+    pub cargo_peak_bytes: usize,
+    pub cargo_allocated_bytes: usize,
+    /// Whether any package selected by both resolvers has different features turned on.
+    pub feature_sets_differ: bool,
+    /// Time for `cargo generate-lockfile` on a workspace, when `--cargo-workspace` is used.
+    pub cargo_workspace_time: f32,
+    pub cargo_workspace_deps: usize,
+    /// Whether the workspace leg picked other versions, or had another outcome,
+    /// than calling the cargo resolver directly.
+    pub cargo_workspace_diverged: bool,
+    /// Packages built for the host in the pubgrub solution, with `--emulate-feature-resolver`.
+    pub pub_host_packages: usize,
+    /// Packages, split by whether they are built for the host, that get different features
+    /// from the emulated feature resolver than from cargo's. Empty unless the workspace leg
+    /// picked the same versions as pubgrub.
+    pub feature_resolver_differences: Option<usize>,
+    /// For roots without a solution that do have one when `links` is ignored,
+    /// the two versions that use the same `links` value in that solution.
+    pub links_conflict: Option<String>,
+    /// Time spent looking for `links_conflict`, which is not part of `time`.
+    pub links_conflict_time: f32,
     pub cargo_check_pub_lock_time: f32,
     pub pub_check_cargo_lock_time: f32,
 }
@@ -0,0 +1,232 @@
+//! Reading `Cargo.lock` files from real projects, to replay them against the index.
+
+use std::{collections::BTreeSet, path::Path};
+
+use cargo::util::interning::InternedString;
+
+use crate::{
+    cargo_past_result, cargo_resolver,
+    names::{new_bucket, Names},
+    pub_past_result, Index, IndexMapLookup, LockPolicy, PastResult,
+};
+
+#[derive(serde::Deserialize)]
+struct RawLockFile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: semver::Version,
+    /// Missing for path and workspace packages.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// As written in the lock, `name`, `name version` or `name version (source)`.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+impl LockedPackage {
+    pub fn is_registry(&self) -> bool {
+        self.source.as_deref().map_or(false, |s| {
+            s.starts_with("registry+") || s.starts_with("sparse+")
+        })
+    }
+}
+
+pub struct LockFile {
+    pub packages: Vec<LockedPackage>,
+}
+
+impl LockFile {
+    pub fn read<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let raw: RawLockFile = toml::from_str(&std::fs::read_to_string(path)?)?;
+        Ok(Self {
+            packages: raw.package,
+        })
+    }
+
+    /// The locked versions of every package that comes from a registry.
+    pub fn past_result(&self) -> PastResult {
+        let mut results = PastResult::default();
+        for p in self.packages.iter().filter(|p| p.is_registry()) {
+            results
+                .entry(p.name.as_str().into())
+                .or_default()
+                .insert(p.version.clone());
+        }
+        results
+    }
+
+    /// The packages that depend on any of `names`, directly or through other packages.
+    pub fn dependents(&self, names: &BTreeSet<String>) -> BTreeSet<String> {
+        let mut dependents = BTreeSet::new();
+        let mut stack: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+        while let Some(name) = stack.pop() {
+            for p in &self.packages {
+                let depends_on_name = p
+                    .dependencies
+                    .iter()
+                    .any(|d| d.split(' ').next() == Some(name));
+                if depends_on_name && dependents.insert(p.name.clone()) {
+                    stack.push(&p.name);
+                }
+            }
+        }
+        dependents
+    }
+
+    /// The locked registry packages that are in `crates`, which can be used as roots.
+    /// The workspace members that actually own the lock are path packages, which are not in
+    /// the index, so each of these is resolved the way it was as a dependency instead.
+    pub fn roots(&self, crates: &IndexMapLookup) -> Vec<(InternedString, semver::Version)> {
+        self.packages
+            .iter()
+            .filter(|p| p.is_registry())
+            .filter(|p| {
+                crates
+                    .get(p.name.as_str())
+                    .map_or(false, |vers| vers.contains_key(&p.version))
+            })
+            .map(|p| (p.name.as_str().into(), p.version.clone()))
+            .collect()
+    }
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct ReplaySummary {
+    pub name: InternedString,
+    pub ver: semver::Version,
+    pub lock_policy: LockPolicy,
+    pub pub_time: f32,
+    pub pub_succeeded: bool,
+    pub pub_deps: usize,
+    pub pub_churn: usize,
+    pub cargo_time: f32,
+    pub cargo_succeeded: bool,
+    pub cargo_deps: usize,
+    pub cargo_churn: usize,
+}
+
+/// A lock only has what its workspace needed of each package, which never includes its dev
+/// dependencies. The lock does not say which features were turned on, so the roots are resolved
+/// with their default features, rather than with all features like `process_crate_version` does.
+///
+/// This is biased for packages whose default features were turned off: if their defaults turn on
+/// an optional dependency that is not in the lock, `LockPolicy::Strict` reports the lock as not
+/// reproduced, even though it is right for how the package was used.
+fn dependency_root<'c>(crt: InternedString, ver: &semver::Version) -> Names<'c> {
+    new_bucket(crt, ver.into(), false).with_default_features()
+}
+
+/// Resolves `crt@ver` with both resolvers, locked to the versions in `lock` by the `LockPolicy`
+/// of `dp`. With `LockPolicy::Strict` succeeding means that the resolver could reproduce the
+/// lock for this root, with `LockPolicy::Prefer` the churn shows how far it had to move.
+pub fn replay(
+    dp: &mut Index,
+    crt: InternedString,
+    ver: semver::Version,
+    lock: &PastResult,
+) -> ReplaySummary {
+    let root = dependency_root(crt, &ver);
+    dp.reset();
+    dp.lock_to(lock.clone());
+
+    dp.reset_time();
+    let res = dp.resolve(root, ver.clone());
+    let pub_time = dp.duration();
+    let pub_deps = res
+        .as_ref()
+        .map_or(0, |map| map.keys().filter(|k| k.is_real()).count());
+    let pub_churn = res
+        .as_ref()
+        .map_or(0, |map| churn(lock, &pub_past_result(map)));
+
+    dp.reset_time();
+    let cargo_out = cargo_resolver::resolve_as_dependency(crt, &ver, dp);
+    let cargo_time = dp.duration();
+    let cargo_churn = cargo_out
+        .as_ref()
+        .map_or(0, |r| churn(lock, &cargo_past_result(r)));
+
+    ReplaySummary {
+        name: crt,
+        ver,
+        lock_policy: dp.lock_policy,
+        pub_time,
+        pub_succeeded: res.is_ok(),
+        pub_deps,
+        pub_churn,
+        cargo_time,
+        cargo_succeeded: cargo_out.is_ok(),
+        cargo_deps: cargo_out.as_ref().map_or(0, |r| r.iter().count()),
+        cargo_churn,
+    }
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct UpdateSummary {
+    pub name: InternedString,
+    pub ver: semver::Version,
+    pub pub_time: f32,
+    pub pub_succeeded: bool,
+    pub pub_churn: usize,
+    pub cargo_time: f32,
+    pub cargo_succeeded: bool,
+    pub cargo_churn: usize,
+}
+
+/// Like `cargo update -p`, resolves `crt@ver` preferring the versions in `lock` except for the
+/// crates in `unlock`, which are free to move.
+pub fn replay_update(
+    dp: &mut Index,
+    crt: InternedString,
+    ver: semver::Version,
+    lock: &PastResult,
+    unlock: &BTreeSet<String>,
+) -> UpdateSummary {
+    let root = dependency_root(crt, &ver);
+    dp.reset();
+    dp.preferred = lock
+        .iter()
+        .filter(|(name, _)| !unlock.contains(name.as_str()))
+        .map(|(name, vers)| (*name, vers.clone()))
+        .collect();
+
+    dp.reset_time();
+    let res = dp.resolve(root, ver.clone());
+    let pub_time = dp.duration();
+    let pub_churn = res
+        .as_ref()
+        .map_or(0, |map| churn(lock, &pub_past_result(map)));
+
+    dp.reset_time();
+    let cargo_out = cargo_resolver::resolve_as_dependency(crt, &ver, dp);
+    let cargo_time = dp.duration();
+    let cargo_churn = cargo_out
+        .as_ref()
+        .map_or(0, |r| churn(lock, &cargo_past_result(r)));
+
+    UpdateSummary {
+        name: crt,
+        ver,
+        pub_time,
+        pub_succeeded: res.is_ok(),
+        pub_churn,
+        cargo_time,
+        cargo_succeeded: cargo_out.is_ok(),
+        cargo_churn,
+    }
+}
+
+/// How many of the `(crate, version)` pairs in `after` are not in `lock`.
+/// Pairs that are only in `lock` are not counted, as most of a lock is usually for other roots.
+pub fn churn(lock: &PastResult, after: &PastResult) -> usize {
+    after
+        .iter()
+        .flat_map(|(name, vers)| vers.iter().map(move |v| (name, v)))
+        .filter(|(name, v)| !lock.get(*name).map_or(false, |l| l.contains(*v)))
+        .count()
+}
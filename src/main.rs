@@ -1,12 +1,25 @@
 use crossbeam::channel::unbounded;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::{sync::mpsc, thread, time::Instant};
 
 use benchmark_from_crates::{
-    index_data, process_crate_version, read_index::read_index, Index, Mode, OutputSummary,
+    cargo_workspace::CargoWorkspace,
+    compare::{read_summary, Comparison},
+    index_data,
+    lockfile::{self, LockFile},
+    minimize::{self, case_from_file_name, write_pubgrub_ron_file, Disagreement, Predicate},
+    offline, process_crate_version_repeated,
+    read_index::{export_snapshot, read_index, read_snapshot, read_test_file},
+    registry_server::Registry,
+    snapshot::{self, Snapshot},
+    Index, IndexMapLookup, LockPolicy, Mode, OutputSummary, Prioritization, VERSION_SET_KIND,
 };
-use clap::Parser;
+use cargo::util::interning::InternedString;
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
 use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use pubgrub::PubGrubError;
 use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
@@ -20,6 +33,14 @@ struct Args {
     #[arg(long, short, value_enum, default_value_t = Mode::All)]
     mode: Mode,
 
+    /// The heuristic used to decide which package to resolve next.
+    #[arg(long, value_enum, default_value_t = Prioritization::Default)]
+    prioritization: Prioritization,
+
+    /// Whether the lock checks, and `replay-lock`, require the locked versions or only prefer them.
+    #[arg(long, value_enum, default_value_t = LockPolicy::Strict)]
+    lock_policy: LockPolicy,
+
     /// Sets the number of threads to be used in the rayon threadpool.
     #[clap(long, short, default_value_t = 0)]
     threads: usize,
@@ -31,20 +52,319 @@ struct Args {
     /// Use a particular refspec from the index to process.
     #[clap(long)]
     commit: Option<String>,
+
+    /// Read the index from a file made by the `export` command, instead of the git checkout.
+    #[clap(long, conflicts_with = "commit")]
+    snapshot: Option<PathBuf>,
+
+    /// Resolve each crate version this many times, after a discarded warmup run,
+    /// and report the median time along with the min and max.
+    #[clap(long, default_value_t = 1)]
+    repeat: usize,
+
+    /// Keep the results of `get_dependencies` between the roots processed on each thread.
+    /// The cache is cleared every 1000 roots. With `--repeat`, the warmup run fills the cache,
+    /// so the measured runs are fully cached.
+    #[clap(long)]
+    cache_dependencies: bool,
+
+    /// Also run `cargo generate-lockfile` on a workspace for each root, against a local
+    /// sparse registry, and report where it differs from calling the cargo resolver directly.
+    #[clap(long)]
+    cargo_workspace: bool,
+
+    /// Write the features each resolver turned on to `out/features`,
+    /// for the roots where they differ.
+    #[clap(long)]
+    dump_features: bool,
+
+    /// Leave out the dev dependencies of each root, to resolve only what is needed to build it.
+    #[clap(long)]
+    no_dev_deps: bool,
+
+    /// Work out the features of each pubgrub solution as feature resolver 2 would, with build
+    /// dependencies apart. With `--cargo-workspace` this is compared with cargo's feature resolver.
+    #[clap(long)]
+    emulate_feature_resolver: bool,
+
+    /// Resolve the roots pubgrub finds no solution for again, ignoring `links`,
+    /// and name the two versions that use the same `links` value if that succeeds.
+    #[clap(long)]
+    find_links_conflicts: bool,
+
+    /// Write the dependency graph each resolver found for every root to this directory,
+    /// as Graphviz DOT and as JSON.
+    #[clap(long)]
+    export_graph: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two output CSVs, exiting with an error if the second one is too much slower.
+    Compare {
+        before: PathBuf,
+        after: PathBuf,
+
+        /// Percentage increase in total time that counts as a regression.
+        #[clap(long, default_value_t = 5.0)]
+        threshold: f32,
+
+        /// How many of the biggest regressions and improvements to list.
+        #[clap(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Shrink an `index_ron` file named `name@version.ron` while its root keeps failing.
+    #[command(group(ArgGroup::new("predicate").multiple(false)))]
+    Minimize {
+        file: PathBuf,
+
+        /// Only keep changes where the resolvers disagree in this way, instead of in any way.
+        #[clap(long, value_enum, group = "predicate")]
+        disagreement: Option<Disagreement>,
+
+        /// Instead of a disagreement, keep changes where pubgrub takes more than this many seconds.
+        #[clap(long, group = "predicate")]
+        slower_than: Option<f32>,
+
+        /// Instead of a disagreement, keep changes where the pubgrub solver itself,
+        /// not counting `get_dependencies` and friends, takes more than this many seconds.
+        #[clap(long, group = "predicate")]
+        solver_slower_than: Option<f32>,
+
+        /// Instead of a disagreement, keep changes where pubgrub calls `get_dependencies`
+        /// more than this many times. Unlike the times this does not change from run to run.
+        #[clap(long, group = "predicate")]
+        more_get_dependencies_calls: Option<usize>,
+
+        /// Also write the `get_dependencies` answers for the result to `out/pubgrub_ron`,
+        /// for benchmarking pubgrub on its own.
+        #[clap(long)]
+        pubgrub_ron: bool,
+
+        /// Where to write the result, defaults to `out/minimized/` with the same file name.
+        #[clap(long)]
+        out: Option<PathBuf>,
+    },
+    /// Write the filtered index to a single compressed file, for use with `--snapshot`.
+    Export {
+        /// Defaults to `out/index_<commit hash>.ron.gz`.
+        out: Option<PathBuf>,
+    },
+    /// Resolve the packages in a `Cargo.lock` with both resolvers, locked to that file.
+    ///
+    /// Each package is resolved with its default features, as the lock does not say which were
+    /// used. A package used with its default features turned off can count as not reproduced
+    /// under `--lock-policy strict`, if its defaults need an optional dependency not in the lock.
+    ReplayLock {
+        lock: PathBuf,
+
+        /// Only replay this `name@version`, instead of every registry package in the lock.
+        #[clap(long)]
+        root: Option<String>,
+
+        /// Like `cargo update -p`, let these crates move and only prefer the locked versions
+        /// of everything else, instead of requiring them.
+        #[clap(long)]
+        update: Vec<String>,
+
+        /// Also let the crates that depend on the `--update` crates move.
+        #[clap(long, requires = "update")]
+        update_dependents: bool,
+
+        #[clap(long, default_value = "out_replay_lock.csv")]
+        out: PathBuf,
+    },
+    /// Serve the index as a sparse registry with stub crates, for running real cargo against it.
+    Serve {
+        /// An `index_ron` file, or a compressed snapshot from `export`, to serve
+        /// instead of the whole index.
+        file: Option<PathBuf>,
+
+        #[clap(long, default_value_t = 8000)]
+        port: u16,
+    },
+    /// Rewrite `index_ron` files from before the snapshot header in the current format.
+    Migrate {
+        /// Defaults to everything in `out/index_ron`.
+        files: Vec<PathBuf>,
+    },
+    /// Resolve `out/pubgrub_offline` files with plain `pubgrub::resolve`.
+    ReplayOffline {
+        /// Defaults to everything in `out/pubgrub_offline`.
+        files: Vec<PathBuf>,
+    },
 }
 
 fn main() {
-    let args = Args::parse();
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(args.threads)
-        .build_global()
-        .unwrap();
+    let mut args = Args::parse();
+    match args.command.take() {
+        Some(Command::Compare {
+            before,
+            after,
+            threshold,
+            top,
+        }) => compare(&before, &after, threshold, top),
+        Some(Command::Minimize {
+            file,
+            disagreement,
+            slower_than,
+            solver_slower_than,
+            more_get_dependencies_calls,
+            pubgrub_ron,
+            out,
+        }) => {
+            let predicate = if let Some(seconds) = slower_than {
+                Predicate::SlowerThan(seconds)
+            } else if let Some(seconds) = solver_slower_than {
+                Predicate::SolverSlowerThan(seconds)
+            } else if let Some(calls) = more_get_dependencies_calls {
+                Predicate::MoreGetDependenciesCalls(calls)
+            } else {
+                Predicate::Disagreement(disagreement)
+            };
+            minimize_file(&file, predicate, out, pubgrub_ron)
+        }
+        Some(Command::Export { out }) => export(&args, out),
+        Some(Command::ReplayLock {
+            lock,
+            root,
+            update,
+            update_dependents,
+            out,
+        }) => {
+            let lock = LockFile::read(lock).unwrap();
+            let mut unlock: BTreeSet<String> = update.into_iter().collect();
+            if update_dependents {
+                unlock.extend(lock.dependents(&unlock));
+            }
+            replay_lock(&args, &lock, root, &unlock, &out)
+        }
+        Some(Command::Serve { file, port }) => serve(&args, file, port),
+        Some(Command::Migrate { files }) => migrate(files),
+        Some(Command::ReplayOffline { files }) => replay_offline(files),
+        None => run(args),
+    }
+}
+
+fn compare(before: &Path, after: &Path, threshold: f32, top: usize) {
+    let comparison = Comparison::new(read_summary(before).unwrap(), read_summary(after).unwrap());
+    comparison.print_report(top);
+    let code = comparison.exit_code(threshold);
+    if code != 0 {
+        println!(
+            "!!!!!!!!!! Regression of {:.2}% exceeds threshold of {threshold}% !!!!!!!!!!",
+            comparison.regression_percent()
+        );
+        std::process::exit(code);
+    }
+}
 
+fn minimize_file(file: &Path, predicate: Predicate, out: Option<PathBuf>, pubgrub_ron: bool) {
+    let mut data = snapshot::read_file(file).unwrap();
+    let root = data.header.root.get_or_insert_with(|| {
+        let file_name = file.file_name().unwrap().to_string_lossy();
+        let (name, version) = case_from_file_name(&file_name);
+        snapshot::Root {
+            name: name.to_string(),
+            version,
+        }
+    });
+    let name = InternedString::new(&root.name);
+    let ver = root.version.clone();
     println!(
-        "Running in mode {:?} on {} rayon threads.",
-        &args.mode,
-        rayon::current_num_threads()
+        "Minimizing {name}@{ver} from {} versions",
+        data.versions.len()
     );
+    let Some(versions) = minimize::minimize(data.versions, name, &ver, |dp, root, ver| {
+        predicate.holds(dp, root, ver)
+    }) else {
+        println!("!!!!!!!!!! {predicate:?} does not hold for {name}@{ver} !!!!!!!!!!");
+        std::process::exit(1);
+    };
+    let out = out.unwrap_or_else(|| Path::new("out/minimized").join(file.file_name().unwrap()));
+    if let Some(parent) = out.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    snapshot::write_file(&out, &Snapshot::new(data.header, versions.clone())).unwrap();
+    println!("Wrote {}", out.display());
+    if pubgrub_ron {
+        write_pubgrub_ron_file(&versions, name, &ver);
+        println!("Wrote out/pubgrub_ron/{name}@{ver}.ron");
+    }
+}
+
+fn serve(args: &Args, file: Option<PathBuf>, port: u16) {
+    let data = match file {
+        Some(file) if file.extension().map_or(false, |e| e == "gz") => {
+            read_snapshot(file).unwrap().1
+        }
+        Some(file) => read_test_file(snapshot::read_file(file).unwrap().versions),
+        None => load_index(args).0,
+    };
+    let server = tiny_http::Server::http(("127.0.0.1", port)).unwrap();
+    let addr = server.server_addr().to_ip().unwrap();
+    println!("Serving {} crates at http://{addr}/index/", data.len());
+    println!("Use it from a project with this in `.cargo/config.toml`:");
+    println!();
+    println!("[source.crates-io]");
+    println!("replace-with = \"benchmark\"");
+    println!();
+    println!("[source.benchmark]");
+    println!("registry = \"sparse+http://{addr}/index/\"");
+    Registry::new(&data).serve(&server);
+}
+
+fn migrate(mut files: Vec<PathBuf>) {
+    if files.is_empty() {
+        for entry in std::fs::read_dir("out/index_ron").unwrap() {
+            files.push(entry.unwrap().path());
+        }
+    }
+    let mut migrated = 0;
+    for file in &files {
+        if snapshot::migrate_file(file).unwrap() {
+            migrated += 1;
+        }
+    }
+    println!("Migrated {migrated} of {} files", files.len());
+}
+
+fn replay_offline(mut files: Vec<PathBuf>) {
+    if files.is_empty() {
+        for entry in std::fs::read_dir("out/pubgrub_offline").unwrap() {
+            files.push(entry.unwrap().path());
+        }
+    }
+    for file in &files {
+        let replay = offline::replay(&offline::read_file(file).unwrap());
+        let outcome = match &replay.result {
+            Ok(solution) => format!("solved with {} packages", solution.len()),
+            Err(PubGrubError::NoSolution(_)) => "no solution".to_string(),
+            Err(e) => format!("error: {e}"),
+        };
+        println!(
+            "{}: {} {} {outcome} in {:.3}s",
+            file.display(),
+            replay.root,
+            replay.version,
+            replay.time
+        );
+    }
+}
+
+/// Reads the index after the filters, from the git checkout or from `--snapshot`.
+fn load_index(args: &Args) -> (IndexMapLookup, snapshot::Header) {
+    if let Some(path) = &args.snapshot {
+        let (header, data) = read_snapshot(path).unwrap();
+        for filter in &header.filters {
+            println!("!!!!!!!!!! Snapshot made with filter: {filter} !!!!!!!!!!");
+        }
+        return (data, header);
+    }
+
     let create_filter = if args.with_solana {
         |_name: &str| true
     } else {
@@ -57,28 +377,192 @@ fn main() {
     let mut index =
         crates_index::GitIndex::with_path("index", "https://github.com/rust-lang/crates.io-index")
             .unwrap();
-    if let Some(commit) = args.commit {
-        index.set_commit_from_refspec(&commit).unwrap();
+    if let Some(commit) = &args.commit {
+        index.set_commit_from_refspec(commit).unwrap();
     }
     let data = read_index(&index, create_filter, version_filter);
 
+    let mut filters = vec!["exclude yanked".to_string()];
+    if !args.with_solana {
+        filters.push("exclude solana".to_string());
+    }
+    let header = snapshot::Header {
+        index_commit: Some(index.commit_hex().to_string()),
+        index_commit_time: Some(
+            OffsetDateTime::from(index.time().unwrap())
+                .format(&Rfc3339)
+                .unwrap(),
+        ),
+        filters,
+        ..Default::default()
+    };
+    (data, header)
+}
+
+fn export(args: &Args, out: Option<PathBuf>) {
+    let (data, header) = load_index(args);
+    let out = out.unwrap_or_else(|| {
+        let commit = header.index_commit.as_deref().unwrap_or("unknown");
+        PathBuf::from(format!(
+            "out/index_{}.ron.gz",
+            &commit[..4.min(commit.len())]
+        ))
+    });
+    let start = Instant::now();
+    export_snapshot(&out, &data, header).unwrap();
+    println!(
+        "Wrote {} in {:.1}s",
+        out.display(),
+        start.elapsed().as_secs_f32()
+    );
+}
+
+fn replay_lock(
+    args: &Args,
+    lock: &LockFile,
+    root: Option<String>,
+    unlock: &BTreeSet<String>,
+    out: &Path,
+) {
+    let (data, _) = load_index(args);
+    let roots = match root {
+        Some(root) => {
+            let (name, ver) = root.split_once('@').expect("root should be name@version");
+            vec![(name.into(), ver.parse().unwrap())]
+        }
+        None => lock.roots(&data),
+    };
+    let past_result = lock.past_result();
+    println!(
+        "Replaying {} roots against a lock of {} packages",
+        roots.len(),
+        lock.packages.len()
+    );
+
+    let mut index = Index::new(&data);
+    index.set_lock_policy(args.lock_policy);
+    if !unlock.is_empty() {
+        println!("!!!!!!!!!! Updating {unlock:?} !!!!!!!!!!");
+        let mut out_file = csv::Writer::from_path(out).unwrap();
+        let mut pub_churn = 0;
+        let mut cargo_churn = 0;
+        let mut pub_time = 0.0;
+        let mut cargo_time = 0.0;
+        for (crt, ver) in &roots {
+            let row = lockfile::replay_update(&mut index, *crt, ver.clone(), &past_result, unlock);
+            pub_time += row.pub_time;
+            cargo_time += row.cargo_time;
+            pub_churn += row.pub_churn;
+            cargo_churn += row.cargo_churn;
+            out_file.serialize(row).unwrap();
+        }
+        out_file.flush().unwrap();
+        println!("!!!!!!!!!! Update !!!!!!!!!!");
+        println!("  Pub changed {pub_churn} versions in {pub_time:.2}s");
+        println!("Cargo changed {cargo_churn} versions in {cargo_time:.2}s");
+        return;
+    }
+
+    let mut out_file = csv::Writer::from_path(out).unwrap();
+    let mut pub_time = 0.0;
+    let mut cargo_time = 0.0;
+    let mut pub_reproduced = 0;
+    let mut cargo_reproduced = 0;
+    for (crt, ver) in &roots {
+        let row = lockfile::replay(&mut index, *crt, ver.clone(), &past_result);
+        pub_time += row.pub_time;
+        cargo_time += row.cargo_time;
+        // With `LockPolicy::Prefer` a resolution can succeed by moving away from the lock.
+        pub_reproduced += (row.pub_succeeded && row.pub_churn == 0) as usize;
+        cargo_reproduced += (row.cargo_succeeded && row.cargo_churn == 0) as usize;
+        if row.pub_succeeded != row.cargo_succeeded {
+            println!(
+                "{crt}@{ver}: pub reproduced {}, cargo reproduced {}",
+                row.pub_succeeded, row.cargo_succeeded
+            );
+        }
+        out_file.serialize(row).unwrap();
+    }
+    out_file.flush().unwrap();
+
+    println!("!!!!!!!!!! Replay !!!!!!!!!!");
+    println!(
+        "  Pub reproduced {pub_reproduced}/{} in {pub_time:.2}s",
+        roots.len()
+    );
+    println!(
+        "Cargo reproduced {cargo_reproduced}/{} in {cargo_time:.2}s",
+        roots.len()
+    );
+}
+
+fn run(args: Args) {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .unwrap();
+
+    println!(
+        "Running in mode {:?} on {} rayon threads with {} version sets.",
+        &args.mode,
+        rayon::current_num_threads(),
+        VERSION_SET_KIND
+    );
+    let (data, mut snapshot_header) = load_index(&args);
+    if let Some(f) = &args.filter {
+        snapshot_header
+            .filters
+            .push(format!("roots containing {f}"));
+    }
+    let commit_hex = snapshot_header.index_commit.clone().unwrap_or_default();
+
     let to_prosses: Vec<_> = data
         .par_iter()
         .filter(|(c, _)| args.filter.as_ref().map_or(true, |f| c.contains(f)))
         .flat_map(|(c, v)| v.par_iter().map(|(v, _)| (c.clone(), v)))
         .collect();
 
+    let server = args.cargo_workspace.then(|| {
+        (
+            Registry::new(&data),
+            tiny_http::Server::http(("127.0.0.1", 0)).unwrap(),
+        )
+    });
+
     thread::scope(|s| {
+        let index_url = server.as_ref().map(|(registry, server)| {
+            s.spawn(move || registry.serve(server));
+            format!("http://{}/index/", server.server_addr().to_ip().unwrap())
+        });
         let (out_tx, out_rx) = mpsc::channel::<OutputSummary>();
         let (to_prosses_tx, to_prosses_rx) = unbounded();
-        for _ in 0..rayon::current_num_threads() {
+        for i in 0..rayon::current_num_threads() {
             let to_prosses_rx = to_prosses_rx.clone();
             let out_tx = out_tx.clone();
             let mut index = Index::new(&data);
+            index.set_dependency_cache(args.cache_dependencies);
+            index.set_prioritization(args.prioritization);
+            index.set_lock_policy(args.lock_policy);
+            index.set_snapshot_header(snapshot_header.clone());
+            index.set_dump_features(args.dump_features);
+            index.set_dev_deps(!args.no_dev_deps);
+            index.set_emulate_feature_resolver(args.emulate_feature_resolver);
+            index.set_find_links_conflicts(args.find_links_conflicts);
+            index.set_export_graph(args.export_graph.clone());
+            if let Some(index_url) = &index_url {
+                let dir = Path::new("out/cargo_workspace").join(i.to_string());
+                index.set_cargo_workspace(CargoWorkspace::new(&dir, index_url).unwrap());
+            }
             s.spawn(move || {
                 for (crt, ver) in to_prosses_rx {
                     out_tx
-                        .send(process_crate_version(&mut index, crt, ver, args.mode))
+                        .send(process_crate_version_repeated(
+                            &mut index,
+                            crt,
+                            ver,
+                            args.mode,
+                            args.repeat,
+                        ))
                         .unwrap();
                 }
             });
@@ -106,8 +590,22 @@ fn main() {
             file_name += "_filtered_to_";
             file_name += &f;
         }
+        if args.prioritization != Prioritization::Default {
+            file_name += "_prioritization_";
+            file_name += args.prioritization.to_possible_value().unwrap().get_name();
+        }
+        if args.lock_policy != LockPolicy::Strict {
+            file_name += "_lock_policy_";
+            file_name += args.lock_policy.to_possible_value().unwrap().get_name();
+        }
+        if args.no_dev_deps {
+            file_name += "_no_dev_deps";
+        }
+        if cfg!(feature = "arc_version_set") {
+            file_name += "_arc_version_set";
+        }
         file_name += "_index_hash_";
-        file_name += &index.commit_hex()[..4];
+        file_name += &commit_hex[..4.min(commit_hex.len())];
         file_name += ".csv";
 
         let mut out_file = csv::Writer::from_path(&file_name).unwrap();
@@ -115,17 +613,32 @@ fn main() {
         let mut cargo_cpu_time = 0.0;
         let mut cargo_pub_lock_cpu_time = 0.0;
         let mut pub_cargo_lock_cpu_time = 0.0;
+        let mut cargo_workspace_cpu_time = 0.0;
+        let mut links_conflict_cpu_time = 0.0;
+        let mut cargo_workspace_diverged = 0;
+        let mut feature_sets_differ = 0;
+        let mut feature_resolver_differs = 0;
+        let mut links_conflicts = 0;
         for row in out_rx {
             pb.inc(1);
             pub_cpu_time += row.time;
             cargo_cpu_time += row.cargo_time;
             cargo_pub_lock_cpu_time += row.cargo_check_pub_lock_time;
             pub_cargo_lock_cpu_time += row.pub_check_cargo_lock_time;
+            cargo_workspace_cpu_time += row.cargo_workspace_time;
+            links_conflict_cpu_time += row.links_conflict_time;
+            cargo_workspace_diverged += row.cargo_workspace_diverged as usize;
+            feature_sets_differ += row.feature_sets_differ as usize;
+            links_conflicts += row.links_conflict.is_some() as usize;
+            feature_resolver_differs += row.feature_resolver_differences.map_or(0, |d| d.min(1));
             out_file.serialize(row).unwrap();
         }
         let wall_time = start.elapsed().as_secs_f32();
         out_file.flush().unwrap();
         pb.finish();
+        if let Some(server) = &server {
+            server.unblock();
+        }
 
         println!("!!!!!!!!!! Timings !!!!!!!!!!");
         let p = |n: &str, t: f32| {
@@ -135,12 +648,13 @@ fn main() {
                 println!("{n:>20} time: skipped")
             }
         };
-        println!("        index commit hash: {}", index.commit_hex());
+        println!("        index commit hash: {commit_hex}");
         println!(
             "        index commit time: {}",
-            OffsetDateTime::from(index.time().unwrap())
-                .format(&Rfc3339)
-                .unwrap()
+            snapshot_header
+                .index_commit_time
+                .as_deref()
+                .unwrap_or("unknown")
         );
         println!("               index size: {}", to_prosses.len());
         println!(
@@ -155,6 +669,18 @@ fn main() {
         p("Cargo CPU", cargo_cpu_time);
         p("Cargo check lock CPU", cargo_pub_lock_cpu_time);
         p("Pub check lock CPU", pub_cargo_lock_cpu_time);
+        p("Cargo workspace CPU", cargo_workspace_cpu_time);
+        p("Links conflict CPU", links_conflict_cpu_time);
         p("Wall", wall_time);
+        println!("feature sets differ: {feature_sets_differ}");
+        if args.find_links_conflicts {
+            println!("unresolvable only because of links: {links_conflicts}");
+        }
+        if args.cargo_workspace {
+            println!("cargo workspace diverged: {cargo_workspace_diverged}");
+            if args.emulate_feature_resolver {
+                println!("feature resolver differs: {feature_resolver_differs}");
+            }
+        }
     });
 }
@@ -0,0 +1,101 @@
+//! A wrapper around the global allocator that counts bytes per thread.
+//!
+//! Each worker thread processes one root at a time, so the per thread counts are a good
+//! approximation of the memory used by a single resolution. Memory freed on a different thread
+//! than it was allocated on makes the counts drift, which is why the current value is signed.
+
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    cell::Cell,
+};
+
+thread_local! {
+    static CURRENT: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+    static TOTAL: Cell<usize> = const { Cell::new(0) };
+}
+
+fn record_alloc(size: usize) {
+    let _ = CURRENT.try_with(|current| {
+        let now = current.get().wrapping_add(size as isize);
+        current.set(now);
+        let _ = PEAK.try_with(|peak| {
+            if now > peak.get() {
+                peak.set(now);
+            }
+        });
+    });
+    let _ = TOTAL.try_with(|total| total.set(total.get().wrapping_add(size)));
+}
+
+fn record_dealloc(size: usize) {
+    let _ = CURRENT.try_with(|current| current.set(current.get().wrapping_sub(size as isize)));
+}
+
+pub struct CountingAlloc<A>(pub A);
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAlloc<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.0.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.0.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.dealloc(ptr, layout);
+        record_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.0.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            record_dealloc(layout.size());
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryUsage {
+    /// The most bytes that were live at once, above what was live at the start.
+    pub peak_bytes: usize,
+    /// All bytes allocated, whether or not they were freed again.
+    pub allocated_bytes: usize,
+}
+
+/// Measures the allocations made by the current thread from `start` until `finish`.
+pub struct MemoryMeasurement {
+    start_current: isize,
+    start_total: usize,
+}
+
+impl MemoryMeasurement {
+    pub fn start() -> Self {
+        let start_current = CURRENT.with(|c| c.get());
+        PEAK.with(|p| p.set(start_current));
+        Self {
+            start_current,
+            start_total: TOTAL.with(|t| t.get()),
+        }
+    }
+
+    pub fn finish(&self) -> MemoryUsage {
+        MemoryUsage {
+            peak_bytes: PEAK
+                .with(|p| p.get().saturating_sub(self.start_current))
+                .max(0) as usize,
+            allocated_bytes: TOTAL.with(|t| t.get().wrapping_sub(self.start_total)),
+        }
+    }
+}
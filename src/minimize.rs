@@ -0,0 +1,275 @@
+//! Cross checks pubgrub against cargo on one root, and shrinks an `index_ron` file while some
+//! property of that root keeps holding.
+
+use std::collections::BTreeSet;
+
+use cargo::{core::Summary, util::interning::InternedString};
+use pubgrub::PubGrubError;
+
+use crate::{
+    cargo_past_result, cargo_resolver, index_data,
+    names::{new_bucket, Names},
+    pub_past_result,
+    read_index::read_test_file,
+    Index,
+};
+
+/// The ways in which the two resolvers can fail to agree on a root.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Disagreement {
+    /// Pubgrub failed with something other than `NoSolution`.
+    PubError,
+    /// The solution found by pubgrub does not pass `Index::check`.
+    CheckViolation,
+    /// Only one of the resolvers reported a cyclic package dependency.
+    Cyclic,
+    /// One resolver found a solution and the other did not.
+    Outcome,
+    /// Cargo fails when locked to the versions pubgrub picked.
+    CargoRejectsPubLock,
+    /// Pubgrub fails when locked to the versions cargo picked.
+    PubRejectsCargoLock,
+}
+
+/// Runs both resolvers on `root`, including each one locked to the others result,
+/// and returns the first way in which they disagree.
+pub fn find_disagreement<'c>(
+    dp: &mut Index<'c>,
+    root: Names<'c>,
+    ver: &semver::Version,
+) -> Option<Disagreement> {
+    dp.reset();
+    let res = dp.resolve(root.clone(), ver.clone());
+    let pub_cyclic_package_dependency = if let Ok(map) = res.as_ref() {
+        dp.check_cycles(root.clone(), map)
+    } else {
+        false
+    };
+
+    match res.as_ref() {
+        Ok(map) => {
+            if !dp.check(root.clone(), map) {
+                return Some(Disagreement::CheckViolation);
+            }
+        }
+        Err(PubGrubError::NoSolution(_derivation)) => {}
+        Err(_e) => {
+            return Some(Disagreement::PubError);
+        }
+    }
+    dp.reset_time();
+    let cargo_out = cargo_resolver::resolve(root.crate_().into(), ver, dp);
+
+    let cyclic_package_dependency = &cargo_out
+        .as_ref()
+        .map_err(|e| e.to_string().starts_with("cyclic package dependency"))
+        == &Err(true);
+
+    if cyclic_package_dependency != pub_cyclic_package_dependency {
+        return Some(Disagreement::Cyclic);
+    }
+
+    if !cyclic_package_dependency && res.is_ok() != cargo_out.is_ok() {
+        return Some(Disagreement::Outcome);
+    }
+
+    if let Ok(map) = res.as_ref() {
+        dp.lock_to(pub_past_result(map));
+        dp.reset_time();
+        let cargo_check_pub_lock_out = cargo_resolver::resolve(root.crate_().into(), ver, dp);
+
+        let cyclic_package_dependency_pub_lock = &cargo_check_pub_lock_out
+            .as_ref()
+            .map_err(|e| e.to_string().starts_with("cyclic package dependency"))
+            == &Err(true);
+
+        if !cyclic_package_dependency_pub_lock && cargo_check_pub_lock_out.is_err() {
+            return Some(Disagreement::CargoRejectsPubLock);
+        }
+    }
+    if let Ok(map) = cargo_out.as_ref() {
+        dp.lock_to(cargo_past_result(map));
+        dp.reset_time();
+        if dp.resolve(root, ver.clone()).is_err() {
+            return Some(Disagreement::PubRejectsCargoLock);
+        }
+    }
+
+    None
+}
+
+/// The properties of a root that `minimize` can preserve.
+#[derive(Clone, Copy, Debug)]
+pub enum Predicate {
+    /// The resolvers disagree, in the given way if there is one.
+    Disagreement(Option<Disagreement>),
+    /// Pubgrub takes longer than this many seconds to resolve.
+    SlowerThan(f32),
+    /// Pubgrub spends longer than this many seconds in the solver itself,
+    /// leaving out the time spent in our `DependencyProvider`.
+    SolverSlowerThan(f32),
+    /// Pubgrub asks for the dependencies of more than this many package versions.
+    MoreGetDependenciesCalls(usize),
+}
+
+impl Predicate {
+    pub fn holds<'c>(&self, dp: &mut Index<'c>, root: Names<'c>, ver: &semver::Version) -> bool {
+        match *self {
+            Predicate::Disagreement(kind) => match find_disagreement(dp, root, ver) {
+                Some(found) => kind.map_or(true, |kind| kind == found),
+                None => false,
+            },
+            Predicate::SlowerThan(seconds) => {
+                dp.reset();
+                let _ = dp.resolve(root, ver.clone());
+                dp.duration() > seconds
+            }
+            Predicate::SolverSlowerThan(seconds) => {
+                dp.reset();
+                let _ = dp.resolve(root, ver.clone());
+                dp.duration() - dp.phase_times.get().provider().as_secs_f32() > seconds
+            }
+            Predicate::MoreGetDependenciesCalls(calls) => {
+                dp.reset();
+                let _ = dp.resolve(root, ver.clone());
+                dp.pubgrub_dependencies.borrow().len() > calls
+            }
+        }
+    }
+}
+
+/// Splits an `index_ron` file name of the form `name@version.ron` into its root.
+pub fn case_from_file_name(file_name: &str) -> (&str, semver::Version) {
+    let (name, rest) = file_name.split_once("@").unwrap();
+    let ver = rest.strip_suffix(".ron").unwrap();
+    (name, ver.parse().unwrap())
+}
+
+/// Resolves `name@ver` with pubgrub and writes every `get_dependencies` answer it used to
+/// `out/pubgrub_ron`, and as an `OfflineDependencyProvider` to `out/pubgrub_offline`,
+/// so the case can be replayed without this crate or cargo.
+pub fn write_pubgrub_ron_file(
+    data: &[index_data::Version],
+    name: InternedString,
+    ver: &semver::Version,
+) {
+    let crates = read_test_file(data.iter().cloned());
+    let mut dp = Index::new(&crates);
+    dp.reset();
+    let _ = dp.resolve(new_bucket(name, ver.into(), true), ver.clone());
+    dp.make_pubgrub_ron_file();
+    dp.make_pubgrub_offline_file();
+}
+
+/// Delta debugging over the versions in `data`.
+///
+/// Repeatedly tries dropping a version, or replacing it with a copy that has fewer features or
+/// dependencies, and keeps any change for which `still_fails` holds for the root `name@ver`.
+/// After each kept change only the versions that the last resolution looked at are kept,
+/// so `still_fails` is expected to resolve the root with the `Index` it is given.
+///
+/// Returns `None` if `still_fails` does not hold for the original `data`.
+pub fn minimize(
+    data: Vec<index_data::Version>,
+    name: InternedString,
+    ver: &semver::Version,
+    mut still_fails: impl for<'c> FnMut(&mut Index<'c>, Names<'c>, &semver::Version) -> bool,
+) -> Option<Vec<index_data::Version>> {
+    let mut reproduces = reproducer(name, ver, &mut still_fails);
+    let data = reproduces(&data)?;
+    shrink(data, reproduces, true)
+}
+
+/// Looks for a smaller index on which `still_fails` holds, starting from `data` on which it
+/// does not.
+///
+/// Versions are dropped one after another, whether or not that makes `still_fails` hold, so the
+/// index gets smaller until a failing one turns up. That one is then minimized as by `minimize`.
+/// Returns `None` if no failing index turned up.
+pub fn find_failing_subset(
+    data: Vec<index_data::Version>,
+    name: InternedString,
+    ver: &semver::Version,
+    mut still_fails: impl for<'c> FnMut(&mut Index<'c>, Names<'c>, &semver::Version) -> bool,
+) -> Option<Vec<index_data::Version>> {
+    let reproduces = reproducer(name, ver, &mut still_fails);
+    shrink(data, reproduces, false)
+}
+
+fn reproducer<'a>(
+    name: InternedString,
+    ver: &'a semver::Version,
+    still_fails: &'a mut impl for<'c> FnMut(&mut Index<'c>, Names<'c>, &semver::Version) -> bool,
+) -> impl FnMut(&[index_data::Version]) -> Option<Vec<index_data::Version>> + 'a {
+    move |data: &[index_data::Version]| {
+        let crates = read_test_file(data.iter().cloned());
+        let mut dp = Index::new(&crates);
+        let root = new_bucket(name, ver.into(), true);
+        still_fails(&mut dp, root, ver).then(|| dp.make_index_ron_data())
+    }
+}
+
+/// The search shared by `minimize` and `find_failing_subset`. Until `failing`, dropped versions
+/// stay dropped; after, they are put back unless dropping them kept the failure.
+fn shrink(
+    mut data: Vec<index_data::Version>,
+    mut reproduces: impl FnMut(&[index_data::Version]) -> Option<Vec<index_data::Version>>,
+    mut failing: bool,
+) -> Option<Vec<index_data::Version>> {
+    let mut offset = 0;
+    'data: loop {
+        for i in 0..data.len() {
+            let len = data.len();
+            let i = (i + offset) % len;
+            let removed = data.swap_remove(i);
+            if let Some(smaller) = reproduces(&data) {
+                println!("Failed on removing {i} of {len}");
+                data = smaller;
+                offset = i;
+                failing = true;
+                continue 'data;
+            }
+
+            let mut replacements = Vec::new();
+            replacements.extend(removed.clone().without_features());
+            replacements.extend(
+                (0..removed.features.len()).filter_map(|f| removed.clone().without_a_feature(f)),
+            );
+            replacements.extend(removed.clone().without_deps());
+            replacements
+                .extend((0..removed.deps.len()).filter_map(|d| removed.clone().without_a_dep(d)));
+            for replacement in replacements {
+                if TryInto::<Summary>::try_into(&replacement).is_err() {
+                    continue;
+                }
+                data.push(replacement);
+                if let Some(smaller) = reproduces(&data) {
+                    println!("Failed on a smaller version of {i} of {len}");
+                    data = smaller;
+                    offset = i;
+                    failing = true;
+                    continue 'data;
+                }
+                data.pop();
+            }
+
+            if failing {
+                // Undo the `swap_remove`.
+                data.push(removed);
+                let last = data.len() - 1;
+                data.swap(i, last);
+            }
+        }
+        break;
+    }
+    if !failing {
+        return None;
+    }
+    let names: BTreeSet<_> = data.iter().map(|v| v.name).collect();
+    println!(
+        "Minimized to {} versions of {} crates",
+        data.len(),
+        names.len()
+    );
+    Some(data)
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use cargo::util::interning::InternedString;
 use semver_pubgrub::SemverCompatibility;
 
@@ -92,31 +94,87 @@ pub fn new_links<'c>(crate_: InternedString) -> Names<'c> {
     Names::Links(crate_)
 }
 
+/// Orders by crate name, then like sorting the `Display` output, without building the strings.
+///
+/// Most comparisons are settled by the crate name, or only differ in the features. Otherwise
+/// both sides are compared as the pieces of text `Display` would write, on the stack.
 impl<'c> Ord for Names<'c> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        {
-            match &self {
-                Names::Bucket(c, _, _)
-                | Names::BucketFeatures(c, _, _)
-                | Names::BucketDefaultFeatures(c, _)
-                | Names::Wide(c, _, _, _)
-                | Names::WideFeatures(c, _, _, _, _)
-                | Names::WideDefaultFeatures(c, _, _, _)
-                | Names::Links(c) => *c,
-            }
-        }
-        .cmp({
-            match &other {
-                Names::Bucket(c, _, _)
-                | Names::BucketFeatures(c, _, _)
-                | Names::BucketDefaultFeatures(c, _)
-                | Names::Wide(c, _, _, _)
-                | Names::WideFeatures(c, _, _, _, _)
-                | Names::WideDefaultFeatures(c, _, _, _)
-                | Names::Links(c) => c,
+        self.name().cmp(&other.name()).then_with(|| {
+            match (self.head(), other.head()) {
+                // Everything `Display` writes before the features is the same.
+                (Some(head), Some(other_head)) if head == other_head => self
+                    .tail()
+                    .iter()
+                    .flat_map(|p| p.bytes())
+                    .cmp(other.tail().iter().flat_map(|p| p.bytes())),
+                _ => self.cmp_display(other),
             }
         })
-        .then_with(|| self.to_string().cmp(&other.to_string()))
+    }
+}
+
+/// What `Display` writes between the crate name and the features.
+/// Only `Range:` packages have a parent and a requirement.
+#[derive(PartialEq)]
+struct Head<'c> {
+    parent: Option<InternedString>,
+    compat: SemverCompatibility,
+    req: Option<&'c semver::VersionReq>,
+}
+
+/// Text written to the stack, for the parts of `Display` that are not already strings.
+struct StackStr<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for StackStr<N> {
+    fn default() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+}
+
+impl<const N: usize> StackStr<N> {
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.buf[..self.len]).unwrap()
+    }
+
+    fn write_compat(&mut self, com: &SemverCompatibility) -> &str {
+        use std::fmt::Write as _;
+        self.len = 0;
+        match com {
+            SemverCompatibility::Major(i) => write!(self, "{}.x.y", i),
+            SemverCompatibility::Minor(i) => write!(self, "0.{}.x", i),
+            SemverCompatibility::Patch(i) => write!(self, "0.0.{}", i),
+        }
+        .unwrap();
+        self.as_str()
+    }
+
+    /// Falls back to the heap for requirements too long for the buffer.
+    fn write_req(&mut self, req: &semver::VersionReq) -> std::borrow::Cow<'_, str> {
+        use std::fmt::Write as _;
+        self.len = 0;
+        match write!(self, "{}", req) {
+            Ok(()) => std::borrow::Cow::Borrowed(self.as_str()),
+            Err(_) => std::borrow::Cow::Owned(req.to_string()),
+        }
+    }
+}
+
+impl<const N: usize> std::fmt::Write for StackStr<N> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let end = self.len + s.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(std::fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
     }
 }
 
@@ -127,6 +185,146 @@ impl<'c> PartialOrd for Names<'c> {
 }
 
 impl<'c> Names<'c> {
+    /// The crate name, or the `links` value.
+    fn name(&self) -> InternedString {
+        match self {
+            Names::Links(name) => *name,
+            _ => self.crate_(),
+        }
+    }
+    /// `None` for `Links:` packages, which have nothing between the name and the end.
+    fn head(&self) -> Option<Head<'c>> {
+        match self {
+            Names::Bucket(_, compat, _)
+            | Names::BucketFeatures(_, compat, _)
+            | Names::BucketDefaultFeatures(_, compat) => Some(Head {
+                parent: None,
+                compat: *compat,
+                req: None,
+            }),
+            Names::Wide(_, req, parent, compat)
+            | Names::WideFeatures(_, req, parent, compat, _)
+            | Names::WideDefaultFeatures(_, req, parent, compat) => Some(Head {
+                parent: Some(*parent),
+                compat: *compat,
+                req: Some(*req),
+            }),
+            Names::Links(_) => None,
+        }
+    }
+    /// What `Display` writes after `head`.
+    fn tail(&self) -> [&'c str; 3] {
+        match self {
+            Names::Bucket(_, _, true) => ["/All-FEATURES", "", ""],
+            Names::Bucket(_, _, false) | Names::Wide(..) | Names::Links(_) => ["", "", ""],
+            Names::BucketFeatures(.., FeatureNamespace::Dep(f))
+            | Names::WideFeatures(.., FeatureNamespace::Dep(f)) => ["/", "dep:", *f],
+            Names::BucketFeatures(.., FeatureNamespace::Feat(f))
+            | Names::WideFeatures(.., FeatureNamespace::Feat(f)) => ["/", "", *f],
+            Names::BucketDefaultFeatures(..) | Names::WideDefaultFeatures(..) => {
+                ["/default=true", "", ""]
+            }
+        }
+    }
+    fn wide_req(&self) -> Option<&'c semver::VersionReq> {
+        self.head().and_then(|head| head.req)
+    }
+    /// Compares the `Display` output. Version requirements are only rendered when they differ.
+    fn cmp_display(&self, other: &Self) -> std::cmp::Ordering {
+        let (mut req_buf, mut other_req_buf) = (StackStr::<64>::default(), StackStr::default());
+        let reqs = match (self.wide_req(), other.wide_req()) {
+            (Some(req), Some(other_req)) if req != other_req => {
+                (req_buf.write_req(req), other_req_buf.write_req(other_req))
+            }
+            // Equal requirements are only reached with equal text before them,
+            // so leaving both out does not change the order.
+            _ => (Default::default(), Default::default()),
+        };
+        let (mut compat, mut other_compat) = (StackStr::<24>::default(), StackStr::default());
+        let parts = self.display_parts(&mut compat, &reqs.0);
+        let other_parts = other.display_parts(&mut other_compat, &reqs.1);
+        parts
+            .iter()
+            .flat_map(|p| p.bytes())
+            .cmp(other_parts.iter().flat_map(|p| p.bytes()))
+    }
+    /// The pieces of text `Display` writes, with `req` standing in for the version requirement.
+    fn display_parts<'a>(&'a self, compat: &'a mut StackStr<24>, req: &'a str) -> [&'a str; 11] {
+        fn pad<'a, const N: usize>(parts: [&'a str; N]) -> [&'a str; 11] {
+            let mut out = [""; 11];
+            out[..N].copy_from_slice(&parts);
+            out
+        }
+        fn feat<'c>(feat: &FeatureNamespace<'c>) -> (&'static str, &'c str) {
+            match feat {
+                FeatureNamespace::Dep(f) => ("dep:", *f),
+                FeatureNamespace::Feat(f) => ("", *f),
+            }
+        }
+        match self {
+            Names::Bucket(n, m, a) => {
+                let all = if *a { "/All-FEATURES" } else { "" };
+                pad(["Bucket:", n.as_str(), "@", compat.write_compat(m), all])
+            }
+            Names::BucketFeatures(n, m, f) => {
+                let (dep, f) = feat(f);
+                pad([
+                    "Bucket:",
+                    n.as_str(),
+                    "@",
+                    compat.write_compat(m),
+                    "/",
+                    dep,
+                    f,
+                ])
+            }
+            Names::BucketDefaultFeatures(n, m) => pad([
+                "Bucket:",
+                n.as_str(),
+                "@",
+                compat.write_compat(m),
+                "/default=true",
+            ]),
+            Names::Wide(c, _, parent, com) => pad([
+                "Range:",
+                c.as_str(),
+                "(From:",
+                parent.as_str(),
+                "@",
+                compat.write_compat(com),
+                "):",
+                req,
+            ]),
+            Names::WideFeatures(c, _, parent, com, f) => {
+                let (dep, f) = feat(f);
+                [
+                    "Range:",
+                    c.as_str(),
+                    "(From:",
+                    parent.as_str(),
+                    "@",
+                    compat.write_compat(com),
+                    "):",
+                    req,
+                    "/",
+                    dep,
+                    f,
+                ]
+            }
+            Names::WideDefaultFeatures(c, _, parent, com) => pad([
+                "Range:",
+                c.as_str(),
+                "(From:",
+                parent.as_str(),
+                "@",
+                compat.write_compat(com),
+                "):",
+                req,
+                "/default=true",
+            ]),
+            Names::Links(name) => pad(["Links:", name.as_str()]),
+        }
+    }
     pub fn is_real(&self) -> bool {
         matches!(self, &Self::Bucket(..))
     }
@@ -271,3 +469,87 @@ impl serde::Serialize for Names<'_> {
         serializer.serialize_str(&self.to_string())
     }
 }
+
+/// A cheap stand in for a `Names`, used as the package type given to pubgrub.
+///
+/// Only the `NameInterner` that handed it out can turn it back into a `Names`,
+/// so `Display` only shows the number.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct NameId(u32);
+
+impl std::fmt::Display for NameId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+impl std::fmt::Debug for NameId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self, f)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct NameInterner<'c> {
+    names: Vec<Names<'c>>,
+    ids: HashMap<Names<'c>, NameId, rustc_hash::FxBuildHasher>,
+}
+
+impl<'c> NameInterner<'c> {
+    pub fn intern(&mut self, name: Names<'c>) -> NameId {
+        if let Some(id) = self.ids.get(&name) {
+            return *id;
+        }
+        let id = NameId(
+            self.names
+                .len()
+                .try_into()
+                .expect("more than u32::MAX names"),
+        );
+        self.names.push(name.clone());
+        self.ids.insert(name, id);
+        id
+    }
+
+    pub fn get(&self, name: &Names<'c>) -> Option<NameId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NameId) -> &Names<'c> {
+        &self.names[id.0 as usize]
+    }
+
+    /// Replaces the `#id`s written by `NameId`'s `Display` in `text` with the names they stand for.
+    pub fn replace_ids(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(i) = rest.find('#') {
+            out.push_str(&rest[..i]);
+            let after = &rest[i + 1..];
+            let len = after
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(after.len());
+            let name = after[..len]
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| self.names.get(i));
+            match name {
+                Some(name) => {
+                    out.push_str(&name.to_string());
+                    rest = &after[len..];
+                }
+                None => {
+                    out.push('#');
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    pub fn clear(&mut self) {
+        self.names.clear();
+        self.ids.clear();
+    }
+}
@@ -0,0 +1,55 @@
+//! The `get_dependencies` answers of a resolution as a plain pubgrub `OfflineDependencyProvider`,
+//! so a case can be replayed with `pubgrub::resolve` and nothing from this crate.
+//!
+//! The files in `out/pubgrub_offline` are the RON serialization of an `OfflineFile`: the root
+//! that was resolved, and the provider, with pubgrub's `serde` feature. Packages are the display
+//! strings of our `Names`, and version sets are `SemverPubgrub`. Versions for which `Index`
+//! reported the dependencies as unavailable are left out, which rules them out in the same way.
+//!
+//! A replay explores the same packages as the original resolution, but can take a different
+//! path through them, as the provider picks versions and priorities in its own way.
+
+use std::{path::Path, time::Instant};
+
+use pubgrub::{OfflineDependencyProvider, PubGrubError, SelectedDependencies};
+use ron::ser::PrettyConfig;
+use semver_pubgrub::SemverPubgrub;
+
+pub type Provider = OfflineDependencyProvider<String, SemverPubgrub>;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct OfflineFile {
+    /// The root package that was resolved, as a package of `provider`.
+    pub root: String,
+    pub version: semver::Version,
+    pub provider: Provider,
+}
+
+pub fn read_file<P: AsRef<Path>>(path: P) -> anyhow::Result<OfflineFile> {
+    Ok(ron::de::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+pub fn write_file<P: AsRef<Path>>(path: P, file: &OfflineFile) -> anyhow::Result<()> {
+    let data = ron::ser::to_string_pretty(file, PrettyConfig::new())?;
+    std::fs::write(path, data)?;
+    Ok(())
+}
+
+pub struct Replay {
+    pub root: String,
+    pub version: semver::Version,
+    pub time: f32,
+    pub result: Result<SelectedDependencies<Provider>, PubGrubError<Provider>>,
+}
+
+/// Runs `pubgrub::resolve` on the provider of `file`, from its root.
+pub fn replay(file: &OfflineFile) -> Replay {
+    let start = Instant::now();
+    let result = pubgrub::resolve(&file.provider, file.root.clone(), file.version.clone());
+    Replay {
+        root: file.root.clone(),
+        version: file.version.clone(),
+        time: start.elapsed().as_secs_f32(),
+        result,
+    }
+}
@@ -0,0 +1,89 @@
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum Phase {
+    ChooseVersion,
+    Prioritize,
+    Bucket,
+    BucketFeatures,
+    BucketDefaultFeatures,
+    Wide,
+    WideFeatures,
+    WideDefaultFeatures,
+    Links,
+}
+
+/// Time spent inside each of the `DependencyProvider` callbacks during one resolution.
+/// `get_dependencies` is split by the variant of `Names` it was called with.
+/// The callbacks are short and many, so this adds up `Duration`s rather than seconds in `f32`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PhaseTimes {
+    pub choose_version: Duration,
+    pub prioritize: Duration,
+    pub bucket: Duration,
+    pub bucket_features: Duration,
+    pub bucket_default_features: Duration,
+    pub wide: Duration,
+    pub wide_features: Duration,
+    pub wide_default_features: Duration,
+    pub links: Duration,
+}
+
+impl PhaseTimes {
+    fn get_mut(&mut self, phase: Phase) -> &mut Duration {
+        match phase {
+            Phase::ChooseVersion => &mut self.choose_version,
+            Phase::Prioritize => &mut self.prioritize,
+            Phase::Bucket => &mut self.bucket,
+            Phase::BucketFeatures => &mut self.bucket_features,
+            Phase::BucketDefaultFeatures => &mut self.bucket_default_features,
+            Phase::Wide => &mut self.wide,
+            Phase::WideFeatures => &mut self.wide_features,
+            Phase::WideDefaultFeatures => &mut self.wide_default_features,
+            Phase::Links => &mut self.links,
+        }
+    }
+
+    pub fn get_dependencies(&self) -> Duration {
+        self.bucket
+            + self.bucket_features
+            + self.bucket_default_features
+            + self.wide
+            + self.wide_features
+            + self.wide_default_features
+            + self.links
+    }
+
+    /// Everything that was spent in our provider, as opposed to in pubgrub itself.
+    pub fn provider(&self) -> Duration {
+        self.choose_version + self.prioritize + self.get_dependencies()
+    }
+}
+
+/// Adds the time until it is dropped to `phase`.
+pub struct PhaseTimer<'a> {
+    times: &'a Cell<PhaseTimes>,
+    phase: Phase,
+    start: Instant,
+}
+
+impl<'a> PhaseTimer<'a> {
+    pub fn new(times: &'a Cell<PhaseTimes>, phase: Phase) -> Self {
+        Self {
+            times,
+            phase,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for PhaseTimer<'_> {
+    fn drop(&mut self) {
+        let mut times = self.times.get();
+        *times.get_mut(self.phase) += self.start.elapsed();
+        self.times.set(times);
+    }
+}
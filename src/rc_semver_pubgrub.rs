@@ -1,18 +1,35 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap};
 
 use pubgrub::VersionSet;
 use semver_pubgrub::SemverPubgrub;
 
+// `Rc` is cheaper. `Arc` is there to measure what atomic reference counting costs.
+#[cfg(not(feature = "arc_version_set"))]
+use std::rc::Rc as Shared;
+#[cfg(feature = "arc_version_set")]
+use std::sync::Arc as Shared;
+
+#[cfg(not(feature = "arc_version_set"))]
+pub const VERSION_SET_KIND: &str = "Rc";
+#[cfg(feature = "arc_version_set")]
+pub const VERSION_SET_KIND: &str = "Arc";
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash, serde::Deserialize, serde::Serialize)]
 #[serde(transparent)]
 pub struct RcSemverPubgrub {
-    pub(crate) inner: Rc<SemverPubgrub>,
+    pub(crate) inner: Shared<SemverPubgrub>,
 }
 
+#[cfg(feature = "arc_version_set")]
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<RcSemverPubgrub>();
+};
+
 impl RcSemverPubgrub {
     pub fn new(inner: SemverPubgrub) -> Self {
         Self {
-            inner: Rc::new(inner),
+            inner: Shared::new(inner),
         }
     }
 }
@@ -23,9 +40,11 @@ impl std::fmt::Display for RcSemverPubgrub {
     }
 }
 
+// The empty set and singletons are asked for over and over, so each thread keeps its own.
+// The caches are the same for `Rc` and `Arc`, so only the reference counting differs.
 thread_local! {
     static ARC_SEMVER_PUBGRUB_EMPTY: RefCell<RcSemverPubgrub> = RefCell::new(RcSemverPubgrub {
-        inner: Rc::new(SemverPubgrub::empty()),
+        inner: Shared::new(SemverPubgrub::empty()),
     });
 
     static ARC_SEMVER_PUBGRUB_SINGLETON: RefCell<HashMap<semver::Version, RcSemverPubgrub>> = RefCell::new(HashMap::default());
@@ -51,7 +70,7 @@ impl VersionSet for RcSemverPubgrub {
     }
 
     fn intersection(&self, other: &Self) -> Self {
-        if Rc::ptr_eq(&self.inner, &other.inner) {
+        if Shared::ptr_eq(&self.inner, &other.inner) {
             return self.clone();
         }
         RcSemverPubgrub::new(self.inner.intersection(&other.inner))
@@ -66,21 +85,21 @@ impl VersionSet for RcSemverPubgrub {
     }
 
     fn union(&self, other: &Self) -> Self {
-        if Rc::ptr_eq(&self.inner, &other.inner) {
+        if Shared::ptr_eq(&self.inner, &other.inner) {
             return self.clone();
         }
         RcSemverPubgrub::new(self.inner.union(&other.inner))
     }
 
     fn is_disjoint(&self, other: &Self) -> bool {
-        if Rc::ptr_eq(&self.inner, &other.inner) {
+        if Shared::ptr_eq(&self.inner, &other.inner) {
             return false;
         }
         self.inner.is_disjoint(&other.inner)
     }
 
     fn subset_of(&self, other: &Self) -> bool {
-        if Rc::ptr_eq(&self.inner, &other.inner) {
+        if Shared::ptr_eq(&self.inner, &other.inner) {
             return true;
         }
         self.inner.subset_of(&other.inner)
@@ -1,10 +1,10 @@
-use std::time::Instant;
+use std::{path::Path, time::Instant};
 
 use cargo::{core::Summary, util::interning::InternedString};
 use crates_index::GitIndex;
 use rayon::iter::ParallelIterator;
 
-use crate::{index_data, IndexMapLookup};
+use crate::{index_data, snapshot, IndexMapLookup};
 
 pub fn read_index(
     index: &GitIndex,
@@ -40,7 +40,35 @@ pub fn read_index(
     crates
 }
 
-#[cfg(test)]
+/// Reads an index written by `export_snapshot`, without needing the git checkout.
+pub fn read_snapshot<P: AsRef<Path>>(
+    path: P,
+) -> anyhow::Result<(snapshot::Header, IndexMapLookup)> {
+    println!("Start reading snapshot");
+    let start = Instant::now();
+    let data = snapshot::read_compressed_file(path)?;
+    let crates = read_test_file(data.versions);
+    println!(
+        "Done reading snapshot in {:.1}s",
+        start.elapsed().as_secs_f32()
+    );
+    Ok((data.header, crates))
+}
+
+/// Writes every version in `crates` to one compressed snapshot file.
+pub fn export_snapshot<P: AsRef<Path>>(
+    path: P,
+    crates: &IndexMapLookup,
+    header: snapshot::Header,
+) -> anyhow::Result<()> {
+    let mut versions: Vec<_> = crates
+        .values()
+        .flat_map(|vers| vers.values().map(|(v, _)| v.clone()))
+        .collect();
+    versions.sort_unstable_by(|a, b| (a.name, &*a.vers).cmp(&(b.name, &*b.vers)));
+    snapshot::write_compressed_file(path, &snapshot::Snapshot::new(header, versions))
+}
+
 pub fn read_test_file(iter: impl IntoIterator<Item = index_data::Version>) -> IndexMapLookup {
     let mut deps = IndexMapLookup::default();
 
@@ -0,0 +1,259 @@
+//! Serves an `IndexMapLookup` as a sparse registry over HTTP, so that real cargo can resolve
+//! against exactly the data the benchmark used.
+//!
+//! The `.crate` files are generated stubs: a `Cargo.toml` with the dependencies, features and
+//! `links` of the version, an empty `src/lib.rs`, and a `build.rs` if the crate has `links`.
+//! Their checksums are the checksums of the stubs, not of the real crates.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Write as _,
+    sync::Mutex,
+};
+
+use cargo::util::interning::InternedString;
+use crates_index::DependencyKind;
+use flate2::{write::GzEncoder, Compression};
+use sha2::{Digest, Sha256};
+
+use crate::{index_data, IndexMapLookup};
+
+/// The path of a crate in the index, relative to the index root.
+pub fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.len() {
+        1 => format!("1/{name}"),
+        2 => format!("2/{name}"),
+        3 => format!("3/{}/{name}", &name[..1]),
+        _ => format!("{}/{}/{name}", &name[..2], &name[2..4]),
+    }
+}
+
+pub struct Registry<'c> {
+    crates: &'c IndexMapLookup,
+    /// Lower case names, as cargo asks for them.
+    names: HashMap<String, InternedString>,
+    /// The checksums of the stubs handed out so far. The stubs themselves are made again
+    /// when they are downloaded, so a long run does not keep them all in memory.
+    checksums: Mutex<HashMap<(InternedString, semver::Version), [u8; 32]>>,
+}
+
+impl<'c> Registry<'c> {
+    pub fn new(crates: &'c IndexMapLookup) -> Self {
+        Self {
+            crates,
+            names: crates.keys().map(|n| (n.to_lowercase(), *n)).collect(),
+            checksums: Default::default(),
+        }
+    }
+
+    /// Answers a request to the server at `base_url`, or `None` for a 404.
+    pub fn handle(&self, url: &str, base_url: &str) -> Option<Vec<u8>> {
+        let path = url.split('?').next().unwrap_or(url);
+        if path == "/index/config.json" {
+            let config = serde_json::json!({
+                "dl": format!("{base_url}/dl/{{crate}}/{{version}}"),
+            });
+            return Some(config.to_string().into_bytes());
+        }
+        if let Some(path) = path.strip_prefix("/index/") {
+            let name = path.rsplit('/').next()?;
+            if index_path(name) != path {
+                return None;
+            }
+            return self.index_file(name).map(String::into_bytes);
+        }
+        let (name, version) = path.strip_prefix("/dl/")?.split_once('/')?;
+        self.crate_file(name, &version.parse().ok()?)
+    }
+
+    /// The index file for `name`, one line of JSON per version.
+    pub fn index_file(&self, name: &str) -> Option<String> {
+        let name = *self.names.get(&name.to_lowercase())?;
+        let mut out = String::new();
+        for (ver, (version, _)) in self.crates.get(&name)? {
+            let cksum = self.checksum(name, ver, version);
+            let deps: Vec<_> = version.deps.iter().map(index_dependency).collect();
+            let features: BTreeMap<_, Vec<_>> = version
+                .features_raw
+                .iter()
+                .map(|(f, values)| (f.as_str(), values.iter().map(|v| v.as_str()).collect()))
+                .collect();
+            let line = serde_json::json!({
+                "name": version.name.as_str(),
+                "vers": ver.to_string(),
+                "deps": deps,
+                "cksum": index_data::checksum_to_hex(&cksum),
+                "features": features,
+                "yanked": version.yanked,
+                "links": version.links.map(|l| l.as_str()),
+                "rust_version": version.rust_version.map(|r| r.as_str()),
+                "v": 2,
+            });
+            out += &line.to_string();
+            out += "\n";
+        }
+        Some(out)
+    }
+
+    /// The checksum of the stub for `version`, made on first use.
+    fn checksum(
+        &self,
+        name: InternedString,
+        ver: &semver::Version,
+        version: &index_data::Version,
+    ) -> [u8; 32] {
+        let key = (name, ver.clone());
+        if let Some(cksum) = self.checksums.lock().unwrap().get(&key) {
+            return *cksum;
+        }
+        let cksum = Sha256::digest(stub_crate(version)).into();
+        self.checksums.lock().unwrap().insert(key, cksum);
+        cksum
+    }
+
+    /// The generated `.crate` file for `name@ver`. The stubs are made the same way every time,
+    /// so it matches the checksum in the index file.
+    pub fn crate_file(&self, name: &str, ver: &semver::Version) -> Option<Vec<u8>> {
+        let name = *self.names.get(&name.to_lowercase())?;
+        let (version, _) = self.crates.get(&name)?.get(ver)?;
+        Some(stub_crate(version))
+    }
+
+    /// Answers requests until `server.unblock()` is called.
+    pub fn serve(&self, server: &tiny_http::Server) {
+        let base_url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        for request in server.incoming_requests() {
+            let response = match self.handle(request.url(), &base_url) {
+                Some(body) => tiny_http::Response::from_data(body),
+                None => tiny_http::Response::from_data(Vec::new()).with_status_code(404),
+            };
+            let _ = request.respond(response);
+        }
+    }
+}
+
+fn kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Normal => "normal",
+        DependencyKind::Build => "build",
+        DependencyKind::Dev => "dev",
+    }
+}
+
+fn index_dependency(dep: &index_data::Dependency) -> serde_json::Value {
+    let mut out = serde_json::json!({
+        "name": dep.name.as_str(),
+        "req": dep.req.to_string(),
+        "features": dep.features.iter().map(|f| f.as_str()).collect::<Vec<_>>(),
+        "optional": dep.optional,
+        "default_features": dep.default_features,
+        "target": dep.target.map(|t| t.as_str()),
+        "kind": kind_name(dep.kind),
+    });
+    if dep.name != dep.package_name {
+        out["package"] = dep.package_name.as_str().into();
+    }
+    out
+}
+
+/// The `Cargo.toml` of the stub for `version`.
+pub fn manifest(version: &index_data::Version) -> String {
+    let mut package = toml::Table::new();
+    package.insert("name".into(), version.name.as_str().into());
+    package.insert("version".into(), (*version.vers).to_string().into());
+    package.insert("edition".into(), "2015".into());
+    if let Some(links) = version.links {
+        package.insert("links".into(), links.as_str().into());
+        package.insert("build".into(), "build.rs".into());
+    }
+    if let Some(rust_version) = version.rust_version {
+        package.insert("rust-version".into(), rust_version.as_str().into());
+    }
+
+    let mut manifest = toml::Table::new();
+    manifest.insert("package".into(), package.into());
+    let mut targets = toml::Table::new();
+    for dep in version.deps.iter() {
+        let mut table = toml::Table::new();
+        table.insert("version".into(), dep.req.to_string().into());
+        if dep.name != dep.package_name {
+            table.insert("package".into(), dep.package_name.as_str().into());
+        }
+        if dep.optional {
+            table.insert("optional".into(), true.into());
+        }
+        if !dep.default_features {
+            table.insert("default-features".into(), false.into());
+        }
+        if !dep.features.is_empty() {
+            let features: Vec<_> = dep.features.iter().map(|f| f.as_str()).collect();
+            table.insert("features".into(), features.into());
+        }
+        let section = match dep.kind {
+            DependencyKind::Normal => "dependencies",
+            DependencyKind::Build => "build-dependencies",
+            DependencyKind::Dev => "dev-dependencies",
+        };
+        let parent = match dep.target {
+            Some(target) => targets
+                .entry(target.as_str())
+                .or_insert(toml::Table::new().into())
+                .as_table_mut()
+                .unwrap(),
+            None => &mut manifest,
+        };
+        parent
+            .entry(section)
+            .or_insert(toml::Table::new().into())
+            .as_table_mut()
+            .unwrap()
+            .insert(dep.name.to_string(), table.into());
+    }
+    if !targets.is_empty() {
+        manifest.insert("target".into(), targets.into());
+    }
+    let features: toml::Table = version
+        .features_raw
+        .iter()
+        .map(|(f, values)| {
+            let values: Vec<_> = values.iter().map(|v| v.as_str()).collect();
+            (f.to_string(), values.into())
+        })
+        .collect();
+    if !features.is_empty() {
+        manifest.insert("features".into(), features.into());
+    }
+    toml::to_string(&manifest).unwrap()
+}
+
+/// The files of the stub for `version`, other than its `Cargo.toml`.
+pub fn stub_sources(version: &index_data::Version) -> Vec<(&'static str, String)> {
+    let mut files = vec![("src/lib.rs", String::new())];
+    if version.links.is_some() {
+        files.push(("build.rs", "fn main() {}\n".to_string()));
+    }
+    files
+}
+
+/// A `.crate` file with just enough in it for cargo to read and build it.
+fn stub_crate(version: &index_data::Version) -> Vec<u8> {
+    let dir = format!("{}-{}", version.name, *version.vers);
+    let mut files = vec![("Cargo.toml", manifest(version))];
+    files.extend(stub_sources(version));
+
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (path, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("{dir}/{path}"), contents.as_bytes())
+            .unwrap();
+    }
+    let mut encoder = builder.into_inner().unwrap();
+    encoder.flush().unwrap();
+    encoder.finish().unwrap()
+}
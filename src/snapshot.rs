@@ -0,0 +1,156 @@
+//! The on-disk format of the files in `out/index_ron`.
+//!
+//! A snapshot is a RON struct with a `header` describing where the versions came from and the
+//! `versions` themselves, in the same shape as the crates.io index but with default values left
+//! out. For example:
+//!
+//! ```ron
+//! (
+//!     header: (
+//!         format_version: 1,
+//!         index_commit: Some("0123456789abcdef0123456789abcdef01234567"),
+//!         root: Some((name: "a", version: "0.1.0")),
+//!         filters: ["exclude yanked"],
+//!     ),
+//!     versions: [
+//!         (name: "a", vers: "0.1.0", deps: [(name: "b", req: "^1", default_features: true)]),
+//!         (name: "b", vers: "1.0.0"),
+//!     ],
+//! )
+//! ```
+//!
+//! Fields that are added later are optional, and unknown fields are ignored, so older readers
+//! can read newer files. `format_version` is only bumped for changes that older readers would
+//! get wrong, and they refuse to read those files.
+//!
+//! Files from before the header existed are a bare list of versions. They are still read,
+//! with a header that has `format_version: 0`, and `migrate_file` rewrites them.
+//!
+//! Snapshots of a whole index are written without the pretty printing and gzip compressed.
+//! They stay RON rather than a binary format, because binary formats can not skip the default
+//! values that make up most of the index.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read as _, Write as _},
+    path::Path,
+};
+
+use anyhow::bail;
+use flate2::{bufread::GzDecoder, write::GzEncoder, Compression};
+use ron::ser::PrettyConfig;
+
+use crate::index_data;
+
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Header {
+    pub format_version: u32,
+    /// The commit of the crates.io index the versions were read from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_commit: Option<String>,
+    /// When that commit was made, in RFC 3339.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_commit_time: Option<String>,
+    /// The root this file was made to reproduce.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<Root>,
+    /// The filters that were applied while reading the index, in words.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Root {
+    pub name: String,
+    pub version: semver::Version,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Snapshot {
+    pub header: Header,
+    pub versions: Vec<index_data::Version>,
+}
+
+impl Snapshot {
+    pub fn new(header: Header, versions: Vec<index_data::Version>) -> Self {
+        Self {
+            header: Header {
+                format_version: FORMAT_VERSION,
+                ..header
+            },
+            versions,
+        }
+    }
+}
+
+pub fn from_str(data: &str) -> anyhow::Result<Snapshot> {
+    let snapshot = match ron::de::from_str::<Snapshot>(data) {
+        Ok(snapshot) => snapshot,
+        Err(e) => match ron::de::from_str::<Vec<index_data::Version>>(data) {
+            Ok(versions) => Snapshot {
+                header: Header::default(),
+                versions,
+            },
+            Err(_) => return Err(e.into()),
+        },
+    };
+    if snapshot.header.format_version > FORMAT_VERSION {
+        bail!(
+            "snapshot format version {} is newer than the supported {FORMAT_VERSION}",
+            snapshot.header.format_version
+        );
+    }
+    Ok(snapshot)
+}
+
+pub fn to_string(snapshot: &Snapshot) -> anyhow::Result<String> {
+    Ok(ron::ser::to_string_pretty(snapshot, PrettyConfig::new())?)
+}
+
+pub fn read_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Snapshot> {
+    from_str(&std::fs::read_to_string(path)?)
+}
+
+pub fn write_file<P: AsRef<Path>>(path: P, snapshot: &Snapshot) -> anyhow::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    ron::ser::to_writer_pretty(&mut file, snapshot, PrettyConfig::new())?;
+    file.flush()?;
+    Ok(())
+}
+
+pub fn read_compressed_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Snapshot> {
+    let mut data = String::new();
+    GzDecoder::new(BufReader::new(File::open(path)?)).read_to_string(&mut data)?;
+    from_str(&data)
+}
+
+pub fn write_compressed_file<P: AsRef<Path>>(path: P, snapshot: &Snapshot) -> anyhow::Result<()> {
+    let mut file = GzEncoder::new(BufWriter::new(File::create(path)?), Compression::default());
+    ron::ser::to_writer(&mut file, snapshot)?;
+    file.finish()?.flush()?;
+    Ok(())
+}
+
+/// Rewrites a file in the current format, taking the root from a `name@version.ron` file name
+/// if the file does not have one. Returns whether anything changed.
+pub fn migrate_file(path: &Path) -> anyhow::Result<bool> {
+    let mut snapshot = read_file(path)?;
+    if snapshot.header.format_version == FORMAT_VERSION {
+        return Ok(false);
+    }
+    if snapshot.header.root.is_none() {
+        snapshot.header.root = path
+            .file_name()
+            .and_then(|f| f.to_str()?.strip_suffix(".ron")?.split_once('@'))
+            .and_then(|(name, version)| {
+                Some(Root {
+                    name: name.to_string(),
+                    version: version.parse().ok()?,
+                })
+            });
+    }
+    write_file(path, &Snapshot::new(snapshot.header, snapshot.versions))?;
+    Ok(true)
+}
@@ -1,115 +1,34 @@
 use std::path::Path;
 
+use cargo::core::dependency::DepKind;
+
 use super::*;
+use minimize::case_from_file_name;
 
-fn case_from_file_name(file_name: &str) -> (&str, semver::Version) {
-    let (name, rest) = file_name.split_once("@").unwrap();
-    let ver = rest.strip_suffix(".ron").unwrap();
-    (name, ver.parse().unwrap())
-}
+mod fuzz;
 
 fn crates_data_from_file<P: AsRef<Path>>(path: P) -> IndexMapLookup {
-    let data = std::fs::read_to_string(path).unwrap();
-    let data: Vec<index_data::Version> = ron::de::from_str(&data).unwrap();
-    read_test_file(data)
+    read_test_file(snapshot::read_file(path).unwrap().versions)
 }
 
-#[must_use]
-fn check<'c>(dp: &mut Index<'c>, root: Names<'c>, ver: &semver::Version) -> bool {
-    dp.reset();
-    let res = resolve(dp, root.clone(), ver.clone());
-    let pub_cyclic_package_dependency = if let Ok(map) = res.as_ref() {
-        dp.check_cycles(root.clone(), map)
-    } else {
-        false
-    };
-
-    match res.as_ref() {
-        Ok(map) => {
-            if !dp.check(root.clone(), &map) {
-                return false;
-            }
-        }
-
-        Err(PubGrubError::NoSolution(_derivation)) => {
-            // eprintln!("{}", DefaultStringReporter::report(&derivation));
-        }
-        Err(_e) => {
-            return false;
-        }
-    }
-    dp.reset_time();
-    let cargo_out = cargo_resolver::resolve(root.crate_().into(), &ver, dp);
-
-    let cyclic_package_dependency = &cargo_out
-        .as_ref()
-        .map_err(|e| e.to_string().starts_with("cyclic package dependency"))
-        == &Err(true);
-
-    if cyclic_package_dependency != pub_cyclic_package_dependency {
-        return false;
-    }
-
-    if !cyclic_package_dependency && res.is_ok() != cargo_out.is_ok() {
-        return false;
-    }
-
-    if res.is_ok() {
-        dp.past_result = res
-            .as_ref()
-            .map(|map| {
-                let mut results: HashMap<
-                    InternedString,
-                    BTreeSet<semver::Version>,
-                    rustc_hash::FxBuildHasher,
-                > = HashMap::default();
-                for (k, v) in map.iter() {
-                    if k.is_real() {
-                        results.entry(k.crate_()).or_default().insert(v.clone());
-                    }
-                }
-                results
-            })
-            .ok();
-        dp.reset_time();
-        let cargo_check_pub_lock_out = cargo_resolver::resolve(root.crate_().into(), &ver, dp);
-
-        let cyclic_package_dependency_pub_lock = &cargo_check_pub_lock_out
-            .as_ref()
-            .map_err(|e| e.to_string().starts_with("cyclic package dependency"))
-            == &Err(true);
-
-        if !cyclic_package_dependency_pub_lock && !cargo_check_pub_lock_out.is_ok() {
-            return false;
-        }
-    }
-    if cargo_out.is_ok() {
-        dp.past_result = cargo_out
-            .as_ref()
-            .map(|map| {
-                let mut results: HashMap<
-                    InternedString,
-                    BTreeSet<semver::Version>,
-                    rustc_hash::FxBuildHasher,
-                > = HashMap::default();
-                for v in map.iter() {
-                    results
-                        .entry(v.name())
-                        .or_default()
-                        .insert(v.version().clone());
-                }
-                results
-            })
-            .ok();
-        dp.reset_time();
-        let pub_check_cargo_lock_out = resolve(dp, root.clone(), ver.clone());
+fn crates_data_from_str(data: &str) -> IndexMapLookup {
+    read_test_file(snapshot::from_str(data).unwrap().versions)
+}
 
-        if !pub_check_cargo_lock_out.is_ok() {
-            return false;
-        }
-    }
+/// A fresh `Index` over `crates`, ready to resolve the root `name@ver`.
+fn index_for_root<'c>(
+    crates: &'c IndexMapLookup,
+    name: &str,
+    ver: &semver::Version,
+) -> (Index<'c>, Names<'c>) {
+    let mut dp = Index::new(crates);
+    dp.reset();
+    (dp, new_bucket(InternedString::new(name), ver.into(), true))
+}
 
-    true
+#[must_use]
+fn check<'c>(dp: &mut Index<'c>, root: Names<'c>, ver: &semver::Version) -> bool {
+    minimize::find_disagreement(dp, root, ver).is_none()
 }
 
 #[test]
@@ -121,12 +40,12 @@ fn serde_round_trip() {
         let file_name = case.file_name().unwrap().to_string_lossy().to_string();
         eprintln!("Running: {file_name}");
         let raw_data = std::fs::read_to_string(&case).unwrap();
-        let data: Vec<index_data::Version> = ron::de::from_str(&raw_data).unwrap();
-        let raw_data_2 = ron::ser::to_string_pretty(&data, PrettyConfig::new()).unwrap();
-        let crates_1 = read_test_file(data);
-        let data_2: Vec<index_data::Version> = ron::de::from_str(&raw_data_2).unwrap();
-        let crates_2 = read_test_file(data_2);
-        if crates_1 != crates_2 {
+        let data = snapshot::from_str(&raw_data).unwrap();
+        let raw_data_2 = snapshot::to_string(&data).unwrap();
+        let data_2 = snapshot::from_str(&raw_data_2).unwrap();
+        let crates_1 = read_test_file(data.versions);
+        let crates_2 = read_test_file(data_2.versions);
+        if crates_1 != crates_2 || data.header != data_2.header {
             faild.push(file_name);
         } else if raw_data != raw_data_2 {
             let mut file = File::create(&case).unwrap();
@@ -137,6 +56,18 @@ fn serde_round_trip() {
     assert_eq!(faild.as_slice(), &Vec::<String>::new());
 }
 
+#[test]
+fn serde_round_trip_escaped_strings() {
+    let target = r#"cfg(target_os = "windows")"#;
+    let raw_data = r#"(name: "a", deps: [(name: "b", target: "cfg(target_os = \"windows\")")])"#;
+    let data: index_data::Version = ron::de::from_str(raw_data).unwrap();
+    let dep = data.deps.iter().next().unwrap();
+    assert_eq!(dep.target.as_deref(), Some(target));
+    let raw_data_2 = ron::ser::to_string(&data).unwrap();
+    let data_2: index_data::Version = ron::de::from_str(&raw_data_2).unwrap();
+    assert_eq!(data, data_2);
+}
+
 #[test]
 fn named_from_files_pass_tests() {
     // Switch to https://docs.rs/snapbox/latest/snapbox/harness/index.html
@@ -170,90 +101,15 @@ fn named_from_files_pass_without_vers() {
         let (name, ver) = case_from_file_name(&file_name);
         let name = InternedString::new(name);
         eprintln!("Running: {name} @ {ver}");
-        let root = new_bucket(name, (&ver).into(), true);
         let start_time = std::time::Instant::now();
-        let data = std::fs::read_to_string(&case).unwrap();
-        let mut data: Vec<index_data::Version> = ron::de::from_str(&data).unwrap();
-        let mut offset = 0;
-        'data: loop {
-            for i in 0..data.len() {
-                let len = data.len();
-                let i = (i + offset) % len;
-                let removed = data.swap_remove(i);
-                let crates = read_test_file(data.iter().cloned());
-                let mut dp = Index::new(&crates);
-                if !check(&mut dp, root.clone(), &ver) {
-                    data = dp.make_index_ron_data();
-                    offset = i;
-                    println!("Failed on removing {i} of {len}");
-                    continue 'data;
-                };
-                if let Some(without_features) = removed.clone().without_features() {
-                    data.push(without_features);
-                    let crates = read_test_file(data.iter().cloned());
-                    let mut dp = Index::new(&crates);
-                    if !check(&mut dp, root.clone(), &ver) {
-                        data = dp.make_index_ron_data();
-                        offset = i;
-                        println!("Failed on without_features {i} of {len}");
-                        continue 'data;
-                    };
-                    data.pop();
-                }
-                for f in 0..removed.features.len() {
-                    if let Some(without_features) = removed.clone().without_a_feature(f) {
-                        if TryInto::<Summary>::try_into(&without_features).is_ok() {
-                            data.push(without_features);
-                            let crates = read_test_file(data.iter().cloned());
-                            let mut dp = Index::new(&crates);
-                            if !check(&mut dp, root.clone(), &ver) {
-                                data = dp.make_index_ron_data();
-                                offset = i;
-                                println!("Failed on without_a_feature({f}) {i} of {len}");
-                                continue 'data;
-                            };
-                            data.pop();
-                        }
-                    }
-                }
-                if let Some(without_deps) = removed.clone().without_deps() {
-                    if TryInto::<Summary>::try_into(&without_deps).is_ok() {
-                        data.push(without_deps);
-                        let crates = read_test_file(data.iter().cloned());
-                        let mut dp = Index::new(&crates);
-                        if !check(&mut dp, root.clone(), &ver) {
-                            data = dp.make_index_ron_data();
-                            offset = i;
-                            println!("Failed on without_deps {i} of {len}");
-                            continue 'data;
-                        };
-                        data.pop();
-                    }
-                }
-                for d in 0..removed.deps.len() {
-                    if let Some(without_deps) = removed.clone().without_a_dep(d) {
-                        if TryInto::<Summary>::try_into(&without_deps).is_ok() {
-                            data.push(without_deps);
-                            let crates = read_test_file(data.iter().cloned());
-                            let mut dp = Index::new(&crates);
-                            if !check(&mut dp, root.clone(), &ver) {
-                                data = dp.make_index_ron_data();
-                                offset = i;
-                                println!("Failed on without_a_dep({d}) {i} of {len}");
-                                continue 'data;
-                            };
-                            data.pop();
-                        }
-                    }
-                }
-            }
-            break;
+        let data = snapshot::read_file(&case).unwrap();
+        if let Some(versions) =
+            minimize::find_failing_subset(data.versions, name, &ver, |dp, root, ver| {
+                !check(dp, root, ver)
+            })
+        {
+            snapshot::write_file(&case, &snapshot::Snapshot::new(data.header, versions)).unwrap();
         }
-        let crates = read_test_file(data);
-        let mut dp = Index::new(&crates);
-        if !check(&mut dp, root, &ver) {
-            dp.make_index_ron_file();
-        };
 
         eprintln!(" in {}s", start_time.elapsed().as_secs());
     }
@@ -283,3 +139,664 @@ fn all_vers_in_files_pass_tests() {
     }
     assert_eq!(faild.as_slice(), &Vec::<String>::new());
 }
+
+#[test]
+fn cached_dependencies_give_the_same_solutions() {
+    for case in std::fs::read_dir("out/index_ron").unwrap() {
+        let case = case.unwrap().path();
+        let file_name = case.file_name().unwrap().to_string_lossy();
+        let (name, ver) = case_from_file_name(&file_name);
+        let crates = crates_data_from_file(&case);
+        let (dp, root) = index_for_root(&crates, name, &ver);
+        let expected = dp.resolve(root.clone(), ver.clone()).ok();
+
+        let mut dp = Index::new(&crates);
+        dp.set_dependency_cache(true);
+        for _ in 0..2 {
+            dp.reset();
+            let res = dp.resolve(root.clone(), ver.clone()).ok();
+            assert_eq!(res, expected, "{file_name}");
+        }
+        assert!(dp.dependency_cache_hits.get() > 0, "{file_name}");
+    }
+}
+
+#[test]
+fn names_ord_is_consistent() {
+    for case in std::fs::read_dir("out/index_ron").unwrap() {
+        let case = case.unwrap().path();
+        let file_name = case.file_name().unwrap().to_string_lossy();
+        let (name, ver) = case_from_file_name(&file_name);
+        let name = InternedString::new(name);
+        let crates = crates_data_from_file(&case);
+        let mut dp = Index::new(&crates);
+        let root = new_bucket(name, (&ver).into(), true);
+        let _ = check(&mut dp, root, &ver);
+        let names = dp
+            .pubgrub_dependencies
+            .borrow()
+            .iter()
+            .map(|(n, _)| n.clone())
+            .collect::<Vec<_>>();
+        for a in &names {
+            for b in &names {
+                assert_eq!(a.cmp(b).is_eq(), a == b, "{a} vs {b}");
+                assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{a} vs {b}");
+                assert_eq!(a.cmp(b), baseline_cmp(a, b), "{a} vs {b}");
+            }
+        }
+    }
+}
+
+/// The order of `Names` when it compared crate names, then the `Display` strings.
+fn baseline_cmp(a: &Names<'_>, b: &Names<'_>) -> std::cmp::Ordering {
+    let name = |n: &Names<'_>| match n {
+        Names::Links(name) => *name,
+        n => n.crate_(),
+    };
+    name(a)
+        .cmp(&name(b))
+        .then_with(|| a.to_string().cmp(&b.to_string()))
+}
+
+#[test]
+fn names_ord_matches_baseline() {
+    use semver_pubgrub::SemverCompatibility::*;
+    let a = InternedString::new("a");
+    let a_b = InternedString::new("a-b");
+    let req = semver::VersionReq::parse("^1.2").unwrap();
+    let other_req = semver::VersionReq::parse(">=1.10").unwrap();
+    // Too long for the buffer `Ord` writes requirements to.
+    let long_req = semver::VersionReq::parse(
+        ">=1.2.3-alpha.1.very.long.pre.release, <2.0.0-beta.2.another.one.two",
+    )
+    .unwrap();
+    let mut names = Vec::new();
+    for name in [a, a_b] {
+        for com in [
+            Major(9),
+            Major(10),
+            Minor(1),
+            Minor(10),
+            Patch(1),
+            Patch(12),
+        ] {
+            let bucket = new_bucket(name, com, false);
+            names.push(bucket.clone());
+            names.push(new_bucket(name, com, true));
+            names.push(bucket.with_default_features());
+            names.push(bucket.with_features(FeatureNamespace::new("dep:zed")));
+            names.push(bucket.with_features(FeatureNamespace::new("alpha")));
+            names.push(bucket.with_features(FeatureNamespace::new("default-tls")));
+            for req in [&req, &other_req, &long_req] {
+                for parent in [a, a_b] {
+                    let wide = new_wide(name, req, parent, com);
+                    names.push(wide.clone());
+                    names.push(wide.with_default_features());
+                    names.push(wide.with_features(FeatureNamespace::new("dep:zed")));
+                    names.push(wide.with_features(FeatureNamespace::new("alpha")));
+                }
+            }
+        }
+        names.push(new_links(name));
+    }
+    for a in &names {
+        for b in &names {
+            assert_eq!(a.cmp(b), baseline_cmp(a, b), "{a} vs {b}");
+        }
+    }
+}
+
+#[test]
+fn interner_replaces_ids_in_text() {
+    let mut names = NameInterner::default();
+    let a = names.intern(new_bucket(
+        InternedString::new("a"),
+        (&semver::Version::new(1, 2, 3)).into(),
+        true,
+    ));
+    let b = names.intern(new_links(InternedString::new("b")));
+    let text = format!("{a} depends on {b:?}, not #99 or #x");
+    assert_eq!(
+        names.replace_ids(&text),
+        "Bucket:a@1.x.y/All-FEATURES depends on Links:b, not #99 or #x"
+    );
+}
+
+#[test]
+fn snapshot_reads_files_without_header() {
+    let legacy = r#"[(name: "a", deps: [(name: "b", default_features: true)]), (name: "b")]"#;
+    let data = snapshot::from_str(legacy).unwrap();
+    assert_eq!(data.header.format_version, 0);
+    assert_eq!(data.versions.len(), 2);
+    let data_2 = snapshot::from_str(&snapshot::to_string(&data).unwrap()).unwrap();
+    assert_eq!(
+        read_test_file(data.versions),
+        read_test_file(data_2.versions)
+    );
+}
+
+#[test]
+fn compare_joins_runs_and_checks_the_threshold() {
+    let before = "name,ver,time,succeeded,deps,cargo_time
+a,1.0.0,1.0,true,3,0.1
+b,1.0.0,2.0,true,4,0.1
+c,1.0.0,3.0,true,5,0.1
+d,1.0.0,4.0,false,0,0.1
+gone,1.0.0,9.0,true,1,0.1
+";
+    let after = "name,ver,time,succeeded,deps
+d,1.0.0,5.0,true,2
+c,1.0.0,3.0,true,5
+b,1.0.0,2.5,true,4
+a,1.0.0,0.5,true,3
+new,0.1.0,1.0,true,1
+";
+    let comparison = compare::Comparison::new(
+        compare::read_summary_from(before.as_bytes()).unwrap(),
+        compare::read_summary_from(after.as_bytes()).unwrap(),
+    );
+    let names = |rows: &[compare::ComparedRow]| {
+        rows.iter()
+            .map(|r| format!("{}@{}", r.name, r.ver))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(comparison.matched.len(), 4);
+    assert_eq!(names(&comparison.only_before), ["gone@1.0.0"]);
+    assert_eq!(names(&comparison.only_after), ["new@0.1.0"]);
+    let changed: Vec<_> = comparison
+        .matched
+        .iter()
+        .filter(|r| r.outcome_changed())
+        .map(|r| r.before.name.as_str())
+        .collect();
+    assert_eq!(changed, ["d"]);
+
+    assert_eq!(comparison.total_before(), 10.0);
+    assert_eq!(comparison.total_after(), 11.0);
+    assert_eq!(comparison.percentile(50.0), (2.0, 2.5));
+    assert_eq!(comparison.percentile(90.0), (4.0, 5.0));
+    assert!((comparison.regression_percent() - 10.0).abs() < 1e-3);
+    assert_eq!(comparison.exit_code(5.0), 1);
+    assert_eq!(comparison.exit_code(10.5), 0);
+}
+
+#[test]
+fn links_conflicts_are_named() {
+    let data = r#"[
+        (name: "a", deps: [
+            (name: "b", default_features: true),
+            (name: "c", default_features: true),
+        ]),
+        (name: "b", links: Some("x")),
+        (name: "c", links: Some("x")),
+    ]"#;
+    let crates = crates_data_from_str(data);
+    let ver = semver::Version::new(0, 0, 1);
+    let (mut dp, root) = index_for_root(&crates, "a", &ver);
+    assert!(dp.resolve(root.clone(), ver.clone()).is_err());
+    let conflict = dp.find_links_conflict(root, &ver).unwrap();
+    assert_eq!(conflict.links, "x");
+    let mut names = [conflict.first.0, conflict.second.0];
+    names.sort();
+    assert_eq!(names, ["b".into(), "c".into()]);
+}
+
+#[test]
+fn offline_provider_replays_files() {
+    for case in std::fs::read_dir("out/index_ron").unwrap() {
+        let case = case.unwrap().path();
+        let file_name = case.file_name().unwrap().to_string_lossy();
+        let (name, ver) = case_from_file_name(&file_name);
+        let crates = crates_data_from_file(&case);
+        let (dp, root) = index_for_root(&crates, name, &ver);
+        let res = dp.resolve(root.clone(), ver.clone());
+        let data = ron::ser::to_string(&dp.make_offline_file()).unwrap();
+        let file: offline::OfflineFile = ron::de::from_str(&data).unwrap();
+        let replay = offline::replay(&file);
+        assert_eq!(replay.root, root.to_string());
+        assert_eq!(replay.version, ver);
+        // pubgrub is complete, so only having the explored versions does not change the outcome.
+        assert_eq!(res.is_ok(), replay.result.is_ok(), "{file_name}");
+    }
+}
+
+#[test]
+fn registry_server_answers_cargo() {
+    use sha2::{Digest, Sha256};
+    use std::io::Read as _;
+
+    let data = r#"[
+        (name: "abc", vers: "1.0.0", deps: [
+            (name: "json", package_name: "serde_json", req: "^1", default_features: true),
+        ], features: {"std": []}, links: Some("abc")),
+        (name: "serde_json", vers: "1.0.0"),
+    ]"#;
+    let crates = crates_data_from_str(data);
+    let registry = registry_server::Registry::new(&crates);
+    let base_url = "http://127.0.0.1:8000";
+
+    let config = registry.handle("/index/config.json", base_url).unwrap();
+    let config: serde_json::Value = serde_json::from_slice(&config).unwrap();
+    assert_eq!(config["dl"], "http://127.0.0.1:8000/dl/{crate}/{version}");
+
+    let index = registry.handle("/index/3/a/abc", base_url).unwrap();
+    let lines: Vec<serde_json::Value> = String::from_utf8(index)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1);
+    let line = &lines[0];
+    assert_eq!(line["name"], "abc");
+    assert_eq!(line["vers"], "1.0.0");
+    assert_eq!(line["links"], "abc");
+    assert_eq!(line["v"], 2);
+    assert_eq!(line["features"], serde_json::json!({"std": []}));
+    assert_eq!(
+        line["deps"],
+        serde_json::json!([{
+            "name": "json",
+            "package": "serde_json",
+            "req": "^1",
+            "features": [],
+            "optional": false,
+            "default_features": true,
+            "target": null,
+            "kind": "normal",
+        }])
+    );
+    assert!(registry.handle("/index/ab/c/abc", base_url).is_none());
+    assert!(registry.handle("/index/3/n/nop", base_url).is_none());
+
+    let file = registry.handle("/dl/abc/1.0.0", base_url).unwrap();
+    let cksum: [u8; 32] = Sha256::digest(&file).into();
+    assert_eq!(line["cksum"], index_data::checksum_to_hex(&cksum));
+    let mut files = BTreeMap::new();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(&file[..]));
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let path = entry.path().unwrap().to_string_lossy().into_owned();
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents).unwrap();
+        files.insert(path, contents);
+    }
+    let (version, _) = &crates["abc"][&semver::Version::new(1, 0, 0)];
+    assert_eq!(
+        files,
+        BTreeMap::from([
+            (
+                "abc-1.0.0/Cargo.toml".to_string(),
+                registry_server::manifest(version)
+            ),
+            (
+                "abc-1.0.0/build.rs".to_string(),
+                "fn main() {}\n".to_string()
+            ),
+            ("abc-1.0.0/src/lib.rs".to_string(), String::new()),
+        ])
+    );
+    assert!(registry.handle("/dl/abc/2.0.0", base_url).is_none());
+}
+
+#[test]
+fn cargo_workspace_root_files() {
+    let data = r#"[
+        (name: "a", vers: "1.0.0", deps: [
+            (name: "json", package_name: "serde_json", req: "^1", default_features: true),
+            (name: "b", req: "^0.1", optional: true, features: ["x"]),
+            (name: "c", req: "^2", target: Some("cfg(windows)"), default_features: true),
+        ], features: {"opt": ["dep:b"]}, links: Some("a")),
+    ]"#;
+    let crates = crates_data_from_str(data);
+    let (version, _) = &crates["a"][&semver::Version::new(1, 0, 0)];
+    let files = cargo_workspace::root_files(version, false);
+    let paths: Vec<_> = files.iter().map(|(path, _)| *path).collect();
+    assert_eq!(paths, ["Cargo.toml", "src/lib.rs", "build.rs"]);
+
+    let manifest: toml::Table = toml::from_str(&files[0].1).unwrap();
+    let expected: toml::Table = toml::from_str(
+        r#"
+        [package]
+        name = "a"
+        version = "1.0.0"
+        edition = "2015"
+        links = "a"
+        build = "build.rs"
+
+        [dependencies]
+        json = { version = "^1", package = "serde_json" }
+        b = { version = "^0.1", optional = true, default-features = false, features = ["x"] }
+
+        [target."cfg(windows)".dependencies]
+        c = { version = "^2" }
+
+        [features]
+        opt = ["dep:b"]
+
+        [workspace]
+        resolver = "2"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(manifest, expected);
+}
+
+#[test]
+fn feature_sets_agree_on_default_and_implicit_features() {
+    let data = r#"[
+        (name: "a", vers: "1.0.0", deps: [(name: "b", req: "^1", default_features: true)]),
+        (name: "b", vers: "1.0.0", deps: [
+            (name: "c", req: "^1", optional: true, default_features: true),
+        ], features: {"default": ["c"]}),
+        (name: "c", vers: "1.0.0", features: {"std": []}),
+    ]"#;
+    let crates = crates_data_from_str(data);
+    let ver = semver::Version::new(1, 0, 0);
+    let (mut dp, root) = index_for_root(&crates, "a", &ver);
+    let map = dp.resolve(root, ver.clone()).unwrap();
+    dp.reset_time();
+    let resolve = cargo_resolver::resolve("a".into(), &ver, &mut dp).unwrap();
+
+    let pub_sets = features::pub_feature_sets(&map);
+    let cargo_sets = features::cargo_feature_sets(&resolve);
+    assert_eq!(
+        pub_sets[&(InternedString::new("b"), ver.clone())],
+        BTreeSet::from(["c".into(), "default".into()])
+    );
+    assert_eq!(
+        pub_sets[&(InternedString::new("c"), ver.clone())],
+        BTreeSet::new()
+    );
+    assert!(features::differences(&pub_sets, &cargo_sets).is_empty());
+}
+
+#[test]
+fn feature_differences_skip_packages_only_one_resolver_selected() {
+    let key = |name: &str, minor| (InternedString::new(name), semver::Version::new(1, minor, 0));
+    let set = |features: &[&str]| features.iter().map(|f| InternedString::new(f)).collect();
+    let pub_sets = features::FeatureSets::from([
+        (key("b", 0), set(&["x"])),
+        (key("c", 0), set(&[])),
+        (key("d", 0), set(&["y"])),
+    ]);
+    let cargo_sets = features::FeatureSets::from([
+        (key("b", 0), set(&["x"])),
+        (key("c", 1), set(&[])),
+        (key("d", 0), set(&["y", "z"])),
+    ]);
+    let differences = features::differences(&pub_sets, &cargo_sets);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].name.as_str(), "d");
+    assert_eq!(differences[0].cargo, set(&["y", "z"]));
+}
+
+fn graph_key(name: &str) -> graph::PackageKey {
+    (name.into(), semver::Version::new(1, 0, 0))
+}
+
+/// A graph with one edge of the given kind between each pair.
+fn hand_built_graph(edges: &[(&str, &str, DepKind)]) -> graph::DependencyGraph {
+    let mut graph = graph::DependencyGraph {
+        root: graph_key("root"),
+        edges: BTreeMap::new(),
+    };
+    for (from, to, kind) in edges {
+        graph.edges.entry(graph_key(to)).or_default();
+        graph.edges.entry(graph_key(from)).or_default().insert(
+            graph_key(to),
+            graph::Edge {
+                kinds: BTreeSet::from([*kind]),
+                ..Default::default()
+            },
+        );
+    }
+    graph
+}
+
+#[test]
+fn reach_follows_the_kind_of_the_path() {
+    let graph = hand_built_graph(&[
+        ("root", "normal", DepKind::Normal),
+        ("root", "build", DepKind::Build),
+        ("build", "build_normal", DepKind::Normal),
+        ("build", "build_only", DepKind::Normal),
+        ("root", "dev", DepKind::Development),
+        ("dev", "dev_normal", DepKind::Normal),
+        ("dev", "dev_dev", DepKind::Development),
+        ("normal", "normal_dev", DepKind::Development),
+        ("normal", "build_normal", DepKind::Normal),
+    ]);
+    let reach = graph.reach();
+    let normal = graph::Reach {
+        normal: true,
+        ..Default::default()
+    };
+    let build = graph::Reach {
+        build: true,
+        ..Default::default()
+    };
+    let dev = graph::Reach {
+        dev: true,
+        ..Default::default()
+    };
+    assert_eq!(reach[&graph_key("normal")], normal);
+    assert_eq!(reach[&graph_key("build")], build);
+    // Normal dependencies of a build dependency are built for the host as well.
+    assert_eq!(reach[&graph_key("build_only")], build);
+    assert_eq!(
+        reach[&graph_key("build_normal")],
+        graph::Reach {
+            normal: true,
+            build: true,
+            dev: false,
+        }
+    );
+    assert_eq!(reach[&graph_key("dev")], dev);
+    assert_eq!(reach[&graph_key("dev_normal")], dev);
+    // Only the root's dev dependencies count.
+    assert!(!reach.contains_key(&graph_key("dev_dev")));
+    assert!(!reach.contains_key(&graph_key("normal_dev")));
+    assert!(!reach.contains_key(&graph_key("root")));
+
+    let footprint = graph.footprint();
+    assert_eq!(footprint.production, 4);
+    assert_eq!(footprint.build, 3);
+    assert_eq!(footprint.dev_only, 2);
+}
+
+/// Resolves `a@1.0.0` in `data` with pubgrub, and emulates feature resolver 2 on the solution.
+fn emulate_feature_resolver(data: &str, dev_deps: bool) -> feature_resolver::SplitFeatureSets {
+    let crates = crates_data_from_str(data);
+    let ver = semver::Version::new(1, 0, 0);
+    let (mut dp, root) = index_for_root(&crates, "a", &ver);
+    dp.set_dev_deps(dev_deps);
+    let map = dp.resolve(root.clone(), ver).unwrap();
+    feature_resolver::emulate_v2(&dp, &root, &map)
+}
+
+fn features_of(
+    sets: &feature_resolver::SplitFeatureSets,
+    name: &str,
+    for_host: bool,
+) -> Option<Vec<&str>> {
+    let set = sets.get(&(graph_key(name), for_host))?;
+    Some(set.iter().map(|f| f.as_str()).collect())
+}
+
+#[test]
+fn emulated_feature_resolver_skips_weak_deps() {
+    let data = r#"[
+        (name: "a", vers: "1.0.0", deps: [(name: "m", req: "^1", features: ["f"])]),
+        (name: "m", vers: "1.0.0", deps: [(name: "b", req: "^1", optional: true)],
+            features: {"f": ["b?/x"]}),
+        (name: "b", vers: "1.0.0", features: {"x": []}),
+    ]"#;
+    let sets = emulate_feature_resolver(data, true);
+    assert_eq!(features_of(&sets, "m", false), Some(vec!["f"]));
+    assert_eq!(features_of(&sets, "b", false), None);
+}
+
+#[test]
+fn emulated_feature_resolver_keeps_build_deps_apart() {
+    let data = r#"[
+        (name: "a", vers: "1.0.0", deps: [
+            (name: "m", req: "^1"),
+            (name: "m", req: "^1", kind: build, features: ["x"]),
+        ]),
+        (name: "m", vers: "1.0.0", deps: [(name: "n", req: "^1")], features: {"x": []}),
+        (name: "n", vers: "1.0.0"),
+    ]"#;
+    let sets = emulate_feature_resolver(data, true);
+    assert_eq!(features_of(&sets, "m", false), Some(vec![]));
+    assert_eq!(features_of(&sets, "m", true), Some(vec!["x"]));
+    // The dependencies of a build dependency are built for the host as well.
+    assert_eq!(features_of(&sets, "n", false), Some(vec![]));
+    assert_eq!(features_of(&sets, "n", true), Some(vec![]));
+}
+
+#[test]
+fn emulated_feature_resolver_only_follows_dev_deps_of_the_root() {
+    let data = r#"[
+        (name: "a", vers: "1.0.0", deps: [(name: "d", req: "^1", kind: dev)]),
+        (name: "d", vers: "1.0.0", deps: [(name: "e", req: "^1", kind: dev)]),
+        (name: "e", vers: "1.0.0"),
+    ]"#;
+    let sets = emulate_feature_resolver(data, true);
+    assert_eq!(features_of(&sets, "d", false), Some(vec![]));
+    assert_eq!(features_of(&sets, "e", false), None);
+    let sets = emulate_feature_resolver(data, false);
+    assert_eq!(features_of(&sets, "d", false), None);
+}
+
+#[test]
+fn emulated_feature_resolver_keeps_compat_versions_apart() {
+    let data = r#"[
+        (name: "a", vers: "1.0.0", deps: [
+            (name: "foo", req: "^0.1", features: ["x"]),
+            (name: "m", req: "^1"),
+            (name: "m", req: "^1", kind: build),
+        ]),
+        (name: "m", vers: "1.0.0", deps: [(name: "foo", req: ">=0.1, <2", features: ["y"])]),
+        (name: "foo", vers: "0.1.0", features: {"x": [], "y": []}),
+        (name: "foo", vers: "1.0.0", features: {"x": [], "y": []}),
+    ]"#;
+    let sets = emulate_feature_resolver(data, true);
+    let features = |ver, for_host| {
+        let set = &sets[&((InternedString::new("foo"), ver), for_host)];
+        set.iter().map(|f| f.as_str()).collect::<Vec<_>>()
+    };
+    // `m` only asks for features of the version pubgrub picked for it, on both sides.
+    assert_eq!(features(semver::Version::new(0, 1, 0), false), ["x"]);
+    assert_eq!(features(semver::Version::new(1, 0, 0), false), ["y"]);
+    assert_eq!(features(semver::Version::new(1, 0, 0), true), ["y"]);
+    assert!(!sets.contains_key(&(
+        (InternedString::new("foo"), semver::Version::new(0, 1, 0)),
+        true
+    )));
+}
+
+#[test]
+fn graphs_export_edges() {
+    let data = r#"[
+        (name: "a", vers: "1.0.0", deps: [
+            (name: "b", req: "^1", features: ["x"], default_features: true),
+            (name: "c", req: "^1", optional: true),
+            (name: "d", req: "^1", kind: build),
+        ]),
+        (name: "b", vers: "1.0.0", features: {"x": []}),
+        (name: "c", vers: "1.0.0"),
+        (name: "d", vers: "1.0.0"),
+    ]"#;
+    let crates = crates_data_from_str(data);
+    let ver = semver::Version::new(1, 0, 0);
+    let (mut dp, root) = index_for_root(&crates, "a", &ver);
+    let map = dp.resolve(root.clone(), ver.clone()).unwrap();
+    let graph = graph::DependencyGraph::from_pubgrub(&dp, &root, &map);
+
+    let normal = serde_json::json!({"normal": true, "build": false, "dev": false});
+    let build = serde_json::json!({"normal": false, "build": true, "dev": false});
+    let expected = serde_json::json!({
+        "root": "a@1.0.0",
+        "nodes": [
+            {"id": "a@1.0.0", "name": "a", "version": "1.0.0", "reach": null},
+            {"id": "b@1.0.0", "name": "b", "version": "1.0.0", "reach": normal},
+            {"id": "c@1.0.0", "name": "c", "version": "1.0.0", "reach": normal},
+            {"id": "d@1.0.0", "name": "d", "version": "1.0.0", "reach": build},
+        ],
+        "edges": [
+            {
+                "from": "a@1.0.0", "to": "b@1.0.0", "kinds": ["normal"], "features": ["x"],
+                "default_features": true, "optional": false,
+            },
+            {
+                "from": "a@1.0.0", "to": "c@1.0.0", "kinds": ["normal"], "features": [],
+                "default_features": false, "optional": true,
+            },
+            {
+                "from": "a@1.0.0", "to": "d@1.0.0", "kinds": ["build"], "features": [],
+                "default_features": false, "optional": false,
+            },
+        ],
+    });
+    assert_eq!(graph.to_json(), expected);
+    assert_eq!(
+        graph.to_dot(),
+        r#"digraph "a@1.0.0" {
+    "a@1.0.0" [shape=box];
+    "a@1.0.0" -> "b@1.0.0" [label="normal x", style=solid];
+    "a@1.0.0" -> "c@1.0.0" [label="normal optional", style=solid];
+    "a@1.0.0" -> "d@1.0.0" [label="build", style=dashed];
+    "b@1.0.0";
+    "c@1.0.0";
+    "d@1.0.0";
+}
+"#
+    );
+
+    dp.reset_time();
+    let resolve = cargo_resolver::resolve("a".into(), &ver, &mut dp).unwrap();
+    assert_eq!(
+        graph::DependencyGraph::from_cargo(&resolve).to_json(),
+        expected
+    );
+}
+
+#[test]
+fn graph_follows_the_version_picked_for_a_wide_requirement() {
+    let data = r#"[
+        (name: "a", vers: "1.0.0", deps: [
+            (name: "foo", req: "^0.1"),
+            (name: "m", req: "^1"),
+        ]),
+        (name: "m", vers: "1.0.0", deps: [(name: "foo", req: ">=0.1, <2")]),
+        (name: "foo", vers: "0.1.0"),
+        (name: "foo", vers: "1.0.0"),
+    ]"#;
+    let crates = crates_data_from_str(data);
+    let ver = semver::Version::new(1, 0, 0);
+    let (mut dp, root) = index_for_root(&crates, "a", &ver);
+    let map = dp.resolve(root.clone(), ver.clone()).unwrap();
+    let graph = graph::DependencyGraph::from_pubgrub(&dp, &root, &map);
+
+    let foo = |ver| (InternedString::new("foo"), ver);
+    let deps = |name| {
+        graph.edges[&graph_key(name)]
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    // Both versions of `foo` are selected, but `m` only uses the one pubgrub picked for it.
+    assert_eq!(
+        deps("a"),
+        [foo(semver::Version::new(0, 1, 0)), graph_key("m")]
+    );
+    assert_eq!(deps("m"), [foo(semver::Version::new(1, 0, 0))]);
+
+    dp.reset_time();
+    let resolve = cargo_resolver::resolve("a".into(), &ver, &mut dp).unwrap();
+    assert_eq!(
+        graph::DependencyGraph::from_cargo(&resolve).to_json(),
+        graph.to_json()
+    );
+}
@@ -0,0 +1,200 @@
+//! Generates small random registries and runs every version in them through the same
+//! comparison as the files in `out/index_ron`.
+//!
+//! The size of the run is controlled by the usual `PROPTEST_CASES` environment variable.
+//! When a disagreement is found it is shrunk by proptest, and the minimal registry is written
+//! to `out/index_ron` so that it becomes one of the regular test cases.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crates_index::DependencyKind;
+use proptest::{
+    prelude::*,
+    test_runner::{Config, TestError, TestRunner},
+};
+
+use super::*;
+
+const CRATES: &[&str] = &["fuzz_a", "fuzz_b", "fuzz_c", "fuzz_d"];
+const VERSIONS: &[&str] = &[
+    "0.0.1", "0.0.2", "0.1.0", "0.1.1", "0.2.0", "1.0.0", "1.1.0", "2.0.0",
+];
+const REQS: &[&str] = &[
+    "^0.0.1",
+    "^0.1",
+    "^0.1.1",
+    "^1",
+    "^1.1",
+    "*",
+    ">=0.1, <2",
+    "=0.2.0",
+];
+const FEATURES: &[&str] = &["default", "f0", "f1"];
+
+/// Has the same shape as the `index_ron` files, so it can be read back as `index_data::Version`.
+#[derive(serde::Serialize, Clone, Debug)]
+struct FuzzDependency {
+    name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    package_name: String,
+    req: String,
+    features: Vec<String>,
+    default_features: bool,
+    kind: DependencyKind,
+    optional: bool,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+struct FuzzVersion {
+    name: String,
+    vers: String,
+    deps: Vec<FuzzDependency>,
+    features: BTreeMap<String, BTreeSet<String>>,
+    links: Option<String>,
+}
+
+fn dependency() -> impl Strategy<Value = FuzzDependency> {
+    (
+        0..CRATES.len(),
+        prop::bool::weighted(0.1),
+        0..REQS.len(),
+        prop::collection::btree_set(0..FEATURES.len(), 0..2),
+        any::<bool>(),
+        prop_oneof![
+            8 => Just(DependencyKind::Normal),
+            1 => Just(DependencyKind::Build),
+            1 => Just(DependencyKind::Dev),
+        ],
+        prop::bool::weighted(0.3),
+    )
+        .prop_map(
+            |(c, renamed, req, features, default_features, kind, optional)| FuzzDependency {
+                name: if renamed {
+                    format!("renamed_{}", CRATES[c])
+                } else {
+                    CRATES[c].to_string()
+                },
+                package_name: if renamed {
+                    CRATES[c].to_string()
+                } else {
+                    String::new()
+                },
+                req: REQS[req].to_string(),
+                features: features
+                    .into_iter()
+                    .map(|f| FEATURES[f].to_string())
+                    .collect(),
+                default_features,
+                kind,
+                optional,
+            },
+        )
+}
+
+/// Each value of a feature is picked by `(kind, dep, feature)`,
+/// where `kind` chooses between `feature`, `dep:dep`, `dep/feature` and `dep?/feature`.
+fn version(name: &'static str, vers: &'static str) -> impl Strategy<Value = FuzzVersion> {
+    (
+        prop::collection::vec(dependency(), 0..4),
+        prop::collection::btree_map(
+            0..FEATURES.len(),
+            prop::collection::vec((0..4u8, 0..4usize, 0..FEATURES.len()), 0..3),
+            0..3,
+        ),
+        prop::bool::weighted(0.1),
+    )
+        .prop_map(move |(deps, features, links)| {
+            let features = features
+                .into_iter()
+                .map(|(f, values)| {
+                    let values = values
+                        .into_iter()
+                        .filter_map(|(kind, d, g)| {
+                            if kind == 0 {
+                                return Some(FEATURES[g].to_string());
+                            }
+                            let dep = &deps.get(d % deps.len().max(1))?.name;
+                            Some(match kind {
+                                1 => format!("dep:{dep}"),
+                                2 => format!("{dep}/{}", FEATURES[g]),
+                                _ => format!("{dep}?/{}", FEATURES[g]),
+                            })
+                        })
+                        .collect();
+                    (FEATURES[f].to_string(), values)
+                })
+                .collect();
+            FuzzVersion {
+                name: name.to_string(),
+                vers: vers.to_string(),
+                deps,
+                features,
+                links: links.then(|| "fuzz_sys".to_string()),
+            }
+        })
+}
+
+fn registry() -> impl Strategy<Value = Vec<FuzzVersion>> {
+    prop::collection::btree_set((0..CRATES.len(), 0..VERSIONS.len()), 1..12).prop_flat_map(|ids| {
+        ids.into_iter()
+            .map(|(c, v)| version(CRATES[c], VERSIONS[v]))
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Goes through the same RON as the files in `out/index_ron`,
+/// dropping versions that cargo would not accept in an index.
+fn to_index_data(registry: &[FuzzVersion]) -> Vec<index_data::Version> {
+    let raw = ron::ser::to_string(registry).unwrap();
+    let data: Vec<index_data::Version> = ron::de::from_str(&raw).unwrap();
+    data.into_iter()
+        .filter(|v| TryInto::<Summary>::try_into(v).is_ok())
+        .collect()
+}
+
+fn first_disagreement(data: &[index_data::Version]) -> Option<(InternedString, semver::Version)> {
+    let crates = read_test_file(data.iter().cloned());
+    let mut dp = Index::new(&crates);
+    for (name, vers) in &crates {
+        for ver in vers.keys() {
+            let root = new_bucket(*name, ver.into(), true);
+            if !check(&mut dp, root, ver) {
+                return Some((*name, ver.clone()));
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn generated_registries_pass_tests() {
+    let mut runner = TestRunner::new(Config {
+        failure_persistence: None,
+        ..Config::default()
+    });
+    let result = runner.run(&registry(), |registry| {
+        let data = to_index_data(&registry);
+        if let Some((name, ver)) = first_disagreement(&data) {
+            return Err(TestCaseError::fail(format!("{name}@{ver}")));
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => {}
+        Err(TestError::Fail(reason, registry)) => {
+            let data = to_index_data(&registry);
+            let (name, ver) = first_disagreement(&data).unwrap();
+            let file_name = format!("out/index_ron/{name}@{ver}.ron");
+            let header = snapshot::Header {
+                root: Some(snapshot::Root {
+                    name: name.to_string(),
+                    version: ver,
+                }),
+                ..Default::default()
+            };
+            snapshot::write_file(&file_name, &snapshot::Snapshot::new(header, data)).unwrap();
+            panic!("{reason}, minimized to {file_name}");
+        }
+        Err(e) => panic!("{e}"),
+    }
+}